@@ -0,0 +1,69 @@
+use std::{collections::HashMap, fs};
+
+use psce_core::Color;
+use psce_search::{Book, BookEntry, PolyglotKeys, encode_move};
+
+use crate::pgn;
+
+/// Builds a Polyglot book from the first `plies` moves of every game in
+/// `pgn`. Moves score 2 for a win, 1 for a draw and 0 for a loss of the
+/// side that played them, and weights are scaled down to fit in 16 bits.
+pub fn run(pgn: String, keys: String, output: String, plies: usize) {
+    let keys = PolyglotKeys::load(keys).expect("Invalid book keys");
+    let pgn = fs::read_to_string(pgn).expect("Failed to read PGN");
+
+    let games = pgn::parse_games(&pgn);
+    println!("Parsed {} games", games.len());
+
+    let mut weights: HashMap<(u64, u16), u64> = HashMap::new();
+
+    for game in &games {
+        let Some(result) = game.result else {
+            continue;
+        };
+
+        let mut pos = game.start.clone();
+
+        for mv in game.moves.iter().take(plies) {
+            let score = match pos.side_to_move() {
+                Color::White => result,
+                Color::Black => 1.0 - result,
+            };
+
+            *weights
+                .entry((keys.key(&pos), encode_move(mv)))
+                .or_default() += (score * 2.0) as u64;
+
+            pos.make_move(mv);
+        }
+    }
+
+    let max_weight = weights.values().copied().max().unwrap_or(0);
+    let scale = |weight: u64| {
+        if max_weight > u16::MAX as u64 {
+            weight * u16::MAX as u64 / max_weight
+        } else {
+            weight
+        }
+    };
+
+    let book = Book::from_entries(
+        weights
+            .into_iter()
+            .filter_map(|((key, mv), weight)| {
+                let weight = scale(weight) as u16;
+
+                (weight > 0).then_some(BookEntry {
+                    key,
+                    mv,
+                    weight,
+                    learn: 0,
+                })
+            })
+            .collect(),
+    );
+
+    fs::write(&output, book.to_bytes()).expect("Failed to write book");
+
+    println!("Wrote {} entries to {}", book.len(), output);
+}
@@ -0,0 +1,107 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use psce_core::Color;
+use psce_search::SearchOptions;
+
+use crate::game;
+
+const FILTER_SCORE: i32 = 2_000;
+
+pub struct DatagenOptions {
+    pub games: usize,
+    pub threads: usize,
+    pub random_plies: usize,
+    pub seed: u64,
+    pub search: SearchOptions,
+}
+
+struct Sample {
+    fen: String,
+    score: i32,
+}
+
+pub fn run(file: File, options: DatagenOptions) {
+    println!(
+        "Generating {} games on {} threads",
+        options.games, options.threads
+    );
+
+    let start = Instant::now();
+    let writer = Mutex::new(BufWriter::new(file));
+    let started = AtomicUsize::new(0);
+    let positions = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for t in 0..options.threads {
+            let (writer, started, positions, options) = (&writer, &started, &positions, &options);
+
+            s.spawn(move || {
+                let mut rng = fastrand::Rng::with_seed(options.seed.wrapping_add(t as u64));
+
+                while started.fetch_add(1, Ordering::Relaxed) < options.games {
+                    let (samples, result) = play_game(&mut rng, options);
+
+                    let mut writer = writer.lock().unwrap();
+                    for sample in &samples {
+                        writeln!(writer, "{} | {} | {:.1}", sample.fen, sample.score, result)
+                            .expect("Failed to write sample");
+                    }
+
+                    let total = positions.fetch_add(samples.len(), Ordering::Relaxed);
+                    if (total + samples.len()) / 10_000 > total / 10_000 {
+                        println!(
+                            "{} positions ({}s)",
+                            total + samples.len(),
+                            start.elapsed().as_secs()
+                        );
+                    }
+                }
+            });
+        }
+    });
+
+    writer
+        .lock()
+        .unwrap()
+        .flush()
+        .expect("Failed to flush output");
+
+    println!(
+        "Wrote {} positions in {}s",
+        positions.load(Ordering::Relaxed),
+        start.elapsed().as_secs()
+    );
+}
+
+/// Plays a single self-play game and returns the filtered samples together
+/// with the result from White's point of view.
+fn play_game(rng: &mut fastrand::Rng, options: &DatagenOptions) -> (Vec<Sample>, f64) {
+    let pos = game::random_opening(rng, options.random_plies);
+    let mut samples = Vec::new();
+
+    let result = game::play(pos, [&options.search, &options.search], |pos, result| {
+        let mv = result.pv[0];
+
+        if result.score.abs() < FILTER_SCORE && !game::is_in_check(pos) && mv.is_quiet() {
+            samples.push(Sample {
+                fen: pos.to_fen(),
+                score: if pos.side_to_move() == Color::White {
+                    result.score
+                } else {
+                    -result.score
+                },
+            });
+        }
+    });
+
+    (samples, result)
+}
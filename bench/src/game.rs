@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use psce_core::{Color, Piece, Position};
+use psce_movegen::MoveGen;
+use psce_search::{SearchOptions, SearchResult, TranspositionTable, search};
+
+const WIN_ADJUDICATION_SCORE: i32 = 2_000;
+const WIN_ADJUDICATION_PLIES: usize = 6;
+const MAX_PLIES: usize = 400;
+
+/// Plays a game from `pos` with `engines[color as usize]` moving for each
+/// side and returns the result from White's point of view. `on_move` is
+/// called with every position and the search result that was played there.
+pub fn play(
+    mut pos: Position,
+    engines: [&SearchOptions; 2],
+    mut on_move: impl FnMut(&Position, &SearchResult),
+) -> f64 {
+    // each side keeps its transposition table for the whole game
+    let engines = engines.map(|options| SearchOptions {
+        tt: Some(Arc::new(TranspositionTable::new(options.hash_mb))),
+        ..options.clone()
+    });
+
+    let mut history = vec![repetition_key(&pos)];
+    let mut winning_streak = (Color::White, 0);
+
+    loop {
+        if MoveGen::legals(&pos).is_empty() {
+            return if is_in_check(&pos) {
+                white_result(!pos.side_to_move())
+            } else {
+                0.5
+            };
+        }
+
+        let key = repetition_key(&pos);
+
+        if pos.halfmove_clock() >= 100
+            || is_insufficient_material(&pos)
+            || history.iter().filter(|&k| *k == key).count() >= 3
+            || history.len() >= MAX_PLIES
+        {
+            return 0.5;
+        }
+
+        let color = pos.side_to_move();
+
+        let Some(result) = search(&pos, &engines[color as usize]) else {
+            return 0.5;
+        };
+
+        if result.score.abs() >= WIN_ADJUDICATION_SCORE {
+            let winner = if result.score > 0 { color } else { !color };
+
+            winning_streak = match winning_streak {
+                (c, n) if c == winner => (c, n + 1),
+                _ => (winner, 1),
+            };
+
+            if winning_streak.1 >= WIN_ADJUDICATION_PLIES {
+                return white_result(winner);
+            }
+        } else {
+            winning_streak.1 = 0;
+        }
+
+        on_move(&pos, &result);
+
+        pos.make_move(&result.pv[0]);
+        history.push(repetition_key(&pos));
+    }
+}
+
+pub fn random_opening(rng: &mut fastrand::Rng, plies: usize) -> Position {
+    'retry: loop {
+        let mut pos = Position::initial();
+
+        for _ in 0..plies {
+            let moves = MoveGen::legals(&pos);
+
+            if moves.is_empty() {
+                continue 'retry;
+            }
+
+            pos.make_move(&moves[rng.usize(..moves.len())]);
+        }
+
+        if !MoveGen::legals(&pos).is_empty() {
+            return pos;
+        }
+    }
+}
+
+pub fn is_in_check(pos: &Position) -> bool {
+    let color = pos.side_to_move();
+    let king_square = pos.king_square(color).expect("should have a king");
+
+    MoveGen::is_attacked(pos, king_square, !color)
+}
+
+fn white_result(winner: Color) -> f64 {
+    match winner {
+        Color::White => 1.0,
+        Color::Black => 0.0,
+    }
+}
+
+fn is_insufficient_material(pos: &Position) -> bool {
+    let bitboards = pos.bitboards();
+
+    let majors_or_pawns = Color::ALL.iter().any(|&c| {
+        [Piece::Pawn, Piece::Rook, Piece::Queen]
+            .iter()
+            .any(|&p| bitboards.piece(c, p).is_not_empty())
+    });
+
+    let minors = Color::ALL
+        .iter()
+        .map(|&c| (bitboards.piece(c, Piece::Knight) | bitboards.piece(c, Piece::Bishop)).count())
+        .sum::<u8>();
+
+    !majors_or_pawns && minors <= 1
+}
+
+fn repetition_key(pos: &Position) -> String {
+    let fen = pos.to_fen();
+    let fields: Vec<_> = fen.split_whitespace().take(4).collect();
+
+    fields.join(" ")
+}
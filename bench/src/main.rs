@@ -1,10 +1,18 @@
-use std::fs::File;
+use std::{fs::File, sync::Arc};
 
 use clap::{Parser, Subcommand};
 use psce_core::Position;
+use psce_search::{Network, Pruning, SearchOptions, Skill};
 
+mod book_builder;
+mod datagen;
+mod game;
+mod match_runner;
 mod perft;
+mod pgn;
 mod suite;
+mod tablebase;
+mod tune;
 
 #[derive(Parser)]
 struct Args {
@@ -27,6 +35,79 @@ enum Command {
         depth: u8,
         #[arg(short = 'n', long)]
         count: Option<usize>,
+        #[arg(long)]
+        network: Option<String>,
+        #[arg(short, long, default_value = "1")]
+        threads: usize,
+    },
+    Datagen {
+        #[arg(short, long)]
+        output: String,
+        #[arg(short, long, default_value = "100")]
+        games: usize,
+        #[arg(short, long, default_value = "1")]
+        threads: usize,
+        #[arg(short, long, default_value = "8")]
+        depth: u8,
+        #[arg(short, long)]
+        nodes: Option<u64>,
+        #[arg(short, long, default_value = "8")]
+        random_plies: usize,
+        #[arg(short, long, default_value = "0")]
+        seed: u64,
+    },
+    /// Plays the default engine (A) against one with the given pruning
+    /// techniques disabled (B), optionally at limited skill levels.
+    Match {
+        #[arg(short, long, default_value = "50")]
+        pairs: usize,
+        #[arg(short, long, default_value = "1")]
+        threads: usize,
+        #[arg(short, long, default_value = "6")]
+        depth: u8,
+        #[arg(short, long)]
+        nodes: Option<u64>,
+        #[arg(short, long, default_value = "8")]
+        random_plies: usize,
+        #[arg(short, long, default_value = "0")]
+        seed: u64,
+        /// Any of: null-move, lmr, pvs, rfp, futility, all
+        #[arg(long, value_delimiter = ',')]
+        disable: Vec<String>,
+        /// Skill level of engine A, full strength by default.
+        #[arg(long)]
+        skill_a: Option<u8>,
+        /// Skill level of engine B, full strength by default.
+        #[arg(long)]
+        skill_b: Option<u8>,
+    },
+    /// Builds a Polyglot opening book from a PGN collection.
+    Book {
+        #[arg(short, long)]
+        pgn: String,
+        /// The 781 Polyglot `Random64` keys as big-endian u64s.
+        #[arg(short, long)]
+        keys: String,
+        #[arg(short, long, default_value = "book.bin")]
+        output: String,
+        #[arg(long, default_value = "20")]
+        plies: usize,
+    },
+    /// Generates endgame tablebases and prints their statistics.
+    Tablebase {
+        #[arg(value_delimiter = ',', default_value = "KQvK,KRvK,KPvK")]
+        endgames: Vec<String>,
+        /// Directory to load tables from and save them to.
+        #[arg(short, long)]
+        dir: Option<String>,
+    },
+    Tune {
+        #[arg(short, long)]
+        file: String,
+        #[arg(short, long, default_value = "tuned.rs")]
+        output: String,
+        #[arg(short, long, default_value = "100")]
+        iterations: usize,
     },
 }
 
@@ -41,10 +122,115 @@ fn main() {
 
             perft::run(pos, depth);
         }
-        Command::Suite { file, depth, count } => {
+        Command::Suite {
+            file,
+            depth,
+            count,
+            network,
+            threads,
+        } => {
+            let file = File::open(file).expect("Failed to open file");
+
+            let options = SearchOptions {
+                depth,
+                network: network
+                    .map(|path| Arc::new(Network::load(path).expect("Invalid network"))),
+                threads,
+                ..Default::default()
+            };
+
+            suite::run(file, &options, count);
+        }
+        Command::Datagen {
+            output,
+            games,
+            threads,
+            depth,
+            nodes,
+            random_plies,
+            seed,
+        } => {
+            let file = File::create(output).expect("Failed to create file");
+
+            datagen::run(
+                file,
+                datagen::DatagenOptions {
+                    games,
+                    threads,
+                    random_plies,
+                    seed,
+                    search: SearchOptions {
+                        depth,
+                        nodes,
+                        ..Default::default()
+                    },
+                },
+            );
+        }
+        Command::Match {
+            pairs,
+            threads,
+            depth,
+            nodes,
+            random_plies,
+            seed,
+            disable,
+            skill_a,
+            skill_b,
+        } => {
+            let a = SearchOptions {
+                depth,
+                nodes,
+                skill: skill_a.map_or_else(Skill::default, Skill::new),
+                ..Default::default()
+            };
+
+            let mut b = SearchOptions {
+                skill: skill_b.map_or_else(Skill::default, Skill::new),
+                ..a.clone()
+            };
+            for technique in disable {
+                match technique.as_str() {
+                    "null-move" => b.pruning.null_move = false,
+                    "lmr" => b.pruning.late_move_reductions = false,
+                    "pvs" => b.pruning.principal_variation_search = false,
+                    "rfp" => b.pruning.reverse_futility = false,
+                    "futility" => b.pruning.futility = false,
+                    "all" => b.pruning = Pruning::NONE,
+                    _ => panic!("Unknown pruning technique: {}", technique),
+                }
+            }
+
+            match_runner::run(
+                &a,
+                &b,
+                match_runner::MatchOptions {
+                    pairs,
+                    threads,
+                    random_plies,
+                    seed,
+                },
+            );
+        }
+        Command::Book {
+            pgn,
+            keys,
+            output,
+            plies,
+        } => {
+            book_builder::run(pgn, keys, output, plies);
+        }
+        Command::Tablebase { endgames, dir } => {
+            tablebase::run(endgames, dir);
+        }
+        Command::Tune {
+            file,
+            output,
+            iterations,
+        } => {
             let file = File::open(file).expect("Failed to open file");
 
-            suite::run(file, depth, count);
+            tune::run(file, output, iterations);
         }
     }
 }
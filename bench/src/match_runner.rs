@@ -0,0 +1,90 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use psce_search::SearchOptions;
+
+use crate::game;
+
+pub struct MatchOptions {
+    pub pairs: usize,
+    pub threads: usize,
+    pub random_plies: usize,
+    pub seed: u64,
+}
+
+#[derive(Default)]
+struct Score {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+/// Plays pairs of games between `a` and `b` from the same random opening
+/// with colors reversed, and reports the score from `a`'s point of view.
+pub fn run(a: &SearchOptions, b: &SearchOptions, options: MatchOptions) {
+    println!(
+        "Playing {} game pairs on {} threads",
+        options.pairs, options.threads
+    );
+
+    let start = Instant::now();
+    let score = Mutex::new(Score::default());
+    let started = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for t in 0..options.threads {
+            let (score, started, options) = (&score, &started, &options);
+
+            s.spawn(move || {
+                let mut rng = fastrand::Rng::with_seed(options.seed.wrapping_add(t as u64));
+
+                while started.fetch_add(1, Ordering::Relaxed) < options.pairs {
+                    let opening = game::random_opening(&mut rng, options.random_plies);
+
+                    let first = game::play(opening.clone(), [a, b], |_, _| {});
+                    let second = 1.0 - game::play(opening, [b, a], |_, _| {});
+
+                    let mut score = score.lock().unwrap();
+
+                    for result in [first, second] {
+                        match result {
+                            1.0 => score.wins += 1,
+                            0.0 => score.losses += 1,
+                            _ => score.draws += 1,
+                        }
+                    }
+
+                    println!(
+                        "+{} ={} -{} ({}s)",
+                        score.wins,
+                        score.draws,
+                        score.losses,
+                        start.elapsed().as_secs()
+                    );
+                }
+            });
+        }
+    });
+
+    let score = score.into_inner().unwrap();
+    let games = score.wins + score.draws + score.losses;
+    let fraction = (score.wins as f64 + score.draws as f64 / 2.0) / games as f64;
+
+    println!(
+        "Score of A vs B: {} - {} - {} [{:.3}] {} games",
+        score.wins, score.losses, score.draws, fraction, games
+    );
+    println!("Elo difference: {:.1}", elo_difference(fraction));
+}
+
+fn elo_difference(fraction: f64) -> f64 {
+    let fraction = fraction.clamp(0.001, 0.999);
+
+    -400.0 * (1.0 / fraction - 1.0).log10()
+}
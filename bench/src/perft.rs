@@ -34,10 +34,7 @@ fn count_nodes(pos: &mut Position, depth: u8) -> u64 {
     for m in moves {
         let undo = pos.make_move(&m);
 
-        let king_square = pos.king_square(own_color).expect("should have a king");
-        let is_king_attacked = MoveGen::is_attacked(pos, king_square, pos.side_to_move());
-
-        if !is_king_attacked {
+        if MoveGen::leaves_king_safe(pos, own_color) {
             nodes += count_nodes(pos, depth - 1);
         }
 
@@ -50,6 +47,37 @@ fn count_nodes(pos: &mut Position, depth: u8) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use psce_core::{PackedMove, Variant};
+
+    /// Checks every 16-bit move against the generated moves, down to `depth`.
+    fn check_packed_moves(pos: &mut Position, depth: u8) {
+        let moves = MoveGen::pseudo_legals(pos);
+
+        for mv in &moves {
+            assert_eq!(mv.pack().unpack(pos), Some(*mv), "{}", pos.to_fen());
+        }
+
+        for repr in 0..=u16::MAX {
+            let packed = PackedMove::new(repr);
+            let generated = packed.unpack(pos).is_some_and(|mv| moves.contains(&mv));
+
+            assert_eq!(
+                pos.is_pseudo_legal(packed),
+                generated,
+                "{} {:?}",
+                pos.to_fen(),
+                packed.unpack(pos)
+            );
+        }
+
+        if depth > 1 {
+            for mv in moves {
+                let undo = pos.make_move(&mv);
+                check_packed_moves(pos, depth - 1);
+                pos.undo_move(&mv, &undo);
+            }
+        }
+    }
 
     #[test]
     fn test_perft_1() {
@@ -112,4 +140,200 @@ mod tests {
         assert_eq!(count_nodes(&mut position, 2), 2079);
         assert_eq!(count_nodes(&mut position, 3), 89890);
     }
+
+    #[test]
+    fn test_perft_chess960() {
+        for (fen, expected) in [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189, 326672],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002, 667366],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10471, 273318],
+            ),
+        ] {
+            let mut position = Position::from_fen(fen).unwrap();
+
+            for (depth, &nodes) in expected.iter().enumerate() {
+                assert_eq!(
+                    count_nodes(&mut position, depth as u8 + 1),
+                    nodes,
+                    "{}",
+                    fen
+                );
+            }
+
+            assert_eq!(position.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_perft_crazyhouse() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        assert_eq!(count_nodes(&mut position, 4), 197281);
+        assert_eq!(count_nodes(&mut position, 5), 4888832);
+
+        let fen = "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        assert_eq!(count_nodes(&mut position, 1), 301);
+        assert_eq!(count_nodes(&mut position, 2), 75353);
+    }
+
+    #[test]
+    fn test_perft_three_check() {
+        let mut position = Position::initial();
+        position.set_variant(Variant::ThreeCheck);
+        assert_eq!(count_nodes(&mut position, 4), 197281);
+
+        // the check from Ra8 ends the game, so black has no replies to it
+        let mut position = Position::from_fen("7k/8/8/8/8/8/8/R6K w - - 1+3 0 1").unwrap();
+        assert_eq!(count_nodes(&mut position, 1), 16);
+        assert_eq!(count_nodes(&mut position, 2), 41);
+    }
+
+    #[test]
+    fn test_perft_king_of_the_hill() {
+        let mut position = Position::from_fen("8/8/8/8/8/2K5/8/k7 w - - 0 1").unwrap();
+        position.set_variant(Variant::KingOfTheHill);
+        assert_eq!(count_nodes(&mut position, 1), 7);
+        assert_eq!(count_nodes(&mut position, 2), 14);
+    }
+
+    #[test]
+    fn test_perft_atomic() {
+        let mut position = Position::initial();
+        position.set_variant(Variant::Atomic);
+        assert_eq!(count_nodes(&mut position, 1), 20);
+        assert_eq!(count_nodes(&mut position, 2), 400);
+        assert_eq!(count_nodes(&mut position, 3), 8902);
+        assert_eq!(count_nodes(&mut position, 4), 197326);
+    }
+
+    #[test]
+    fn test_perft_antichess() {
+        let mut position = Position::initial();
+        position.set_variant(Variant::Antichess);
+        assert_eq!(count_nodes(&mut position, 1), 20);
+        assert_eq!(count_nodes(&mut position, 2), 400);
+        assert_eq!(count_nodes(&mut position, 3), 8067);
+        assert_eq!(count_nodes(&mut position, 4), 153299);
+
+        // the capture is forced and may promote to a king
+        let mut position = Position::from_fen("1n6/P7/8/8/8/8/8/7k w - - 0 1").unwrap();
+        position.set_variant(Variant::Antichess);
+        assert_eq!(count_nodes(&mut position, 1), 5);
+    }
+
+    #[test]
+    fn test_packed_moves() {
+        for (fen, variant) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                Variant::Standard,
+            ),
+            (
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                Variant::Standard,
+            ),
+            ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", Variant::Standard),
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                Variant::Standard,
+            ),
+            (
+                "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1",
+                Variant::Crazyhouse,
+            ),
+            ("1n6/P7/8/8/8/8/8/7k w - - 0 1", Variant::Antichess),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                Variant::Atomic,
+            ),
+        ] {
+            let mut position = Position::from_fen(fen).unwrap();
+            position.set_variant(variant);
+
+            check_packed_moves(&mut position, 2);
+        }
+    }
+
+    /// Checks `gives_check` against making each legal move, down to `depth`.
+    fn check_gives_check(pos: &mut Position, depth: u8) {
+        let color = pos.side_to_move();
+
+        for mv in MoveGen::pseudo_legals(pos) {
+            let gives_check = MoveGen::gives_check(pos, &mv);
+            let undo = pos.make_move(&mv);
+
+            if !MoveGen::leaves_king_safe(pos, color) {
+                pos.undo_move(&mv, &undo);
+                continue;
+            }
+
+            assert_eq!(
+                gives_check,
+                MoveGen::is_in_check(pos, !color),
+                "{} {}",
+                pos.to_fen(),
+                mv
+            );
+
+            if depth > 1 {
+                check_gives_check(pos, depth - 1);
+            }
+
+            pos.undo_move(&mv, &undo);
+        }
+    }
+
+    #[test]
+    fn test_gives_check() {
+        for (fen, variant, depth) in [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                Variant::Standard,
+                2,
+            ),
+            (
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                Variant::Standard,
+                3,
+            ),
+            (
+                "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+                Variant::Standard,
+                3,
+            ),
+            // dxc6 opens the fifth rank for the rook
+            ("8/8/8/k1pP3R/8/8/8/4K3 w - c6 0 1", Variant::Standard, 1),
+            // castling with check from the rook
+            ("5k2/8/8/8/8/8/8/R3K2R w KQ - 0 1", Variant::Standard, 1),
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                Variant::Standard,
+                2,
+            ),
+            (
+                "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1",
+                Variant::Crazyhouse,
+                2,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                Variant::Atomic,
+                2,
+            ),
+        ] {
+            let mut position = Position::from_fen(fen).unwrap();
+            position.set_variant(variant);
+
+            check_gives_check(&mut position, depth);
+        }
+    }
 }
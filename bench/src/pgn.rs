@@ -0,0 +1,228 @@
+use psce_core::{Move, Piece, Position, Square};
+use psce_movegen::MoveGen;
+
+pub struct Game {
+    pub start: Position,
+    pub moves: Vec<Move>,
+    /// Score for white: 1.0 for a win, 0.5 for a draw and 0.0 for a loss.
+    pub result: Option<f64>,
+}
+
+/// Parses every game in a PGN collection. Games with unknown setups or
+/// illegal moves are skipped; comments, variations and NAGs are ignored.
+pub fn parse_games(pgn: &str) -> Vec<Game> {
+    let mut games = Vec::new();
+    let mut fen = None;
+    let mut result = None;
+    let mut movetext = String::new();
+
+    for line in pgn.lines().map(str::trim) {
+        if line.starts_with('[') {
+            if !movetext.trim().is_empty() {
+                games.extend(parse_game(fen.take(), result.take(), &movetext));
+                movetext.clear();
+            }
+
+            if let Some(value) = tag_value(line, "FEN") {
+                fen = Some(value);
+            } else if let Some(value) = tag_value(line, "Result") {
+                result = parse_result(&value);
+            }
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    if !movetext.trim().is_empty() {
+        games.extend(parse_game(fen, result, &movetext));
+    }
+
+    games
+}
+
+fn tag_value(line: &str, name: &str) -> Option<String> {
+    let rest = line.strip_prefix('[')?.strip_prefix(name)?;
+    let value = rest.trim().strip_prefix('"')?;
+
+    Some(value[..value.find('"')?].to_string())
+}
+
+fn parse_result(result: &str) -> Option<f64> {
+    match result {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => None,
+    }
+}
+
+fn parse_game(fen: Option<String>, result: Option<f64>, movetext: &str) -> Option<Game> {
+    let start = match fen {
+        Some(fen) => Position::from_fen(&fen).ok()?,
+        None => Position::initial(),
+    };
+
+    let mut pos = start.clone();
+    let mut moves = Vec::new();
+
+    for token in tokens(movetext) {
+        if parse_result(&token).is_some() || token == "*" {
+            break;
+        }
+
+        let mv = parse_san(&pos, &token).ok()?;
+        pos.make_move(&mv);
+        moves.push(mv);
+    }
+
+    Some(Game {
+        start,
+        moves,
+        result,
+    })
+}
+
+/// Splits movetext into SAN tokens, dropping move numbers, comments,
+/// variations and NAGs.
+fn tokens(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut variation_depth = 0;
+    let mut in_comment = false;
+    let mut in_line_comment = false;
+
+    for c in movetext.chars() {
+        if in_comment {
+            in_comment = c != '}';
+            continue;
+        }
+
+        if in_line_comment {
+            in_line_comment = c != '\n';
+            continue;
+        }
+
+        match c {
+            '{' => in_comment = true,
+            ';' => in_line_comment = true,
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            _ if variation_depth > 0 => {}
+            _ if c.is_whitespace() => tokens.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+
+    tokens.push(current);
+
+    tokens
+        .into_iter()
+        .map(|token| {
+            // strip move numbers like "12." and "12..."
+            match token.rfind('.') {
+                Some(index) => token[index + 1..].to_string(),
+                None => token,
+            }
+        })
+        .filter(|token| !token.is_empty() && !token.starts_with('$'))
+        .collect()
+}
+
+/// Finds the legal move in `pos` written as `san`.
+pub fn parse_san(pos: &Position, san: &str) -> Result<Move, String> {
+    let legal_moves = MoveGen::legals(pos);
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+    if matches!(san, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+        let king_side = san.len() == 3;
+
+        return legal_moves
+            .into_iter()
+            .find(|mv| mv.flags().is_castling() && (mv.to() > mv.from()) == king_side)
+            .ok_or_else(|| format!("Illegal castling: {}", san));
+    }
+
+    let (san, promotion) = match san.split_once('=') {
+        Some((san, promotion)) => (san, promotion.chars().next().and_then(Piece::from_char)),
+        None => match san.chars().last().filter(|c| "QRBN".contains(*c)) {
+            Some(c) => (&san[..san.len() - 1], Piece::from_char(c)),
+            None => (san, None),
+        },
+    };
+
+    let (piece, rest) = match san.chars().next().filter(|c| "KQRBN".contains(*c)) {
+        Some(c) => (Piece::from_char(c).unwrap(), &san[1..]),
+        None => (Piece::Pawn, san),
+    };
+
+    let rest = rest.replace('x', "");
+
+    if rest.len() < 2 {
+        return Err(format!("Invalid move: {}", san));
+    }
+
+    let (disambiguation, to) = rest.split_at(rest.len() - 2);
+    let to: Square = to.parse()?;
+
+    let candidates: Vec<_> = legal_moves
+        .into_iter()
+        .filter(|mv| {
+            mv.piece() == piece
+                && mv.to() == to
+                && mv.promotion() == promotion
+                && !mv.flags().is_castling()
+                && disambiguation.chars().all(|c| {
+                    let c = c.to_string();
+
+                    c.parse() == Ok(mv.from().file()) || c.parse() == Ok(mv.from().rank())
+                })
+        })
+        .collect();
+
+    match candidates[..] {
+        [mv] => Ok(mv),
+        [] => Err(format!("Illegal move: {}", san)),
+        _ => Err(format!("Ambiguous move: {}", san)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_games() {
+        let pgn = r#"[Event "Test"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 {main line} Nc6 (2... d6 3. d4) 3. Bb5 a6 4. O-O $1 Nf6
+5. Re1 1-0
+
+[Event "Test"]
+[FEN "4k3/P7/8/8/8/8/8/4K3 w - - 0 1"]
+[Result "1/2-1/2"]
+
+1. a8=Q+ Kd7 1/2-1/2
+"#;
+
+        let games = parse_games(pgn);
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves.len(), 9);
+        assert_eq!(games[0].result, Some(1.0));
+        assert_eq!(games[0].moves[6].to_string(), "e1g1");
+        assert_eq!(games[1].moves[0].to_string(), "a7a8q");
+        assert_eq!(games[1].result, Some(0.5));
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        let pos = Position::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+
+        assert_eq!(parse_san(&pos, "Rad1").unwrap().to_string(), "a1d1");
+        assert_eq!(parse_san(&pos, "Rhxd1").unwrap().to_string(), "h1d1");
+        assert!(parse_san(&pos, "Rd1").is_err());
+        assert!(parse_san(&pos, "Ra8+").is_ok());
+    }
+}
@@ -5,9 +5,9 @@ use std::{
 };
 
 use psce_core::Position;
-use psce_search::find_best_move;
+use psce_search::{SearchOptions, search};
 
-pub fn run(file: File, depth: u8, count: Option<usize>) {
+pub fn run(file: File, options: &SearchOptions, count: Option<usize>) {
     let mut lines = parse_file(file);
 
     if let Some(count) = count {
@@ -18,15 +18,16 @@ pub fn run(file: File, depth: u8, count: Option<usize>) {
     let mut total_correct = 0;
     let mut total_nodes = 0;
     let mut total_beta_cutoffs = 0;
+    let mut total_first_move_cutoffs = 0;
 
-    println!("Checking {} positions with depth {}", total, depth);
+    println!("Checking {} positions with depth {}", total, options.depth);
 
     let start = Instant::now();
 
     let chunk_size = 20;
 
     for (i, (pos, ranked_moves)) in lines.iter().enumerate() {
-        let engine_result = find_best_move(&pos, depth).expect("No engine result");
+        let engine_result = search(pos, options).expect("No engine result");
         let engine_move = engine_result.pv[0].to_string();
 
         if ranked_moves[0] == engine_move {
@@ -39,6 +40,7 @@ pub fn run(file: File, depth: u8, count: Option<usize>) {
 
         total_nodes += engine_result.stats.nodes;
         total_beta_cutoffs += engine_result.stats.beta_cutoffs;
+        total_first_move_cutoffs += engine_result.stats.first_move_cutoffs;
 
         if (i > 0 && (i + 1) % chunk_size == 0) || i == total - 1 {
             println!(
@@ -68,9 +70,10 @@ pub fn run(file: File, depth: u8, count: Option<usize>) {
         (total_nodes as f64 / elapsed.as_secs_f64()) as u64
     );
     println!(
-        "{} beta cutoffs ({:.2}%)",
+        "{} beta cutoffs ({:.2}%), {:.2}% on first move",
         total_beta_cutoffs,
-        (total_beta_cutoffs as f64 / total_nodes as f64 * 100.0)
+        (total_beta_cutoffs as f64 / total_nodes as f64 * 100.0),
+        (total_first_move_cutoffs as f64 / total_beta_cutoffs as f64 * 100.0)
     );
 }
 
@@ -0,0 +1,69 @@
+use std::{path::Path, time::Instant};
+
+use psce_core::Color;
+use psce_tablebase::{Outcome, Tablebases};
+
+pub fn run(endgames: Vec<String>, dir: Option<String>) {
+    let mut tablebases = Tablebases::new();
+
+    if let Some(dir) = &dir {
+        tablebases
+            .load(Path::new(dir))
+            .expect("Failed to load tables");
+    }
+
+    for endgame in &endgames {
+        let start = Instant::now();
+        tablebases.generate(endgame).expect("Invalid endgame");
+        println!(
+            "Generated {} in {:.2}s",
+            endgame,
+            start.elapsed().as_secs_f64()
+        );
+    }
+
+    if let Some(dir) = &dir {
+        tablebases
+            .save(Path::new(dir))
+            .expect("Failed to save tables");
+    }
+
+    let mut tables: Vec<_> = tablebases.tables().collect();
+    tables.sort_by_key(|table| (table.material().len(), table.material().to_string()));
+
+    println!(
+        "{:<8} {:>10} {:>10} {:>10} {:>10} {:>8}",
+        "Endgame", "Positions", "Wins", "Draws", "Losses", "Longest"
+    );
+
+    for table in tables {
+        let mut counts = [0; 3];
+        let mut longest = 0;
+
+        for (color, outcome) in table.outcomes() {
+            if color != Color::White {
+                continue;
+            }
+
+            match outcome {
+                Outcome::Win(_) => {
+                    counts[0] += 1;
+                    longest = longest.max(outcome.moves().unwrap());
+                }
+                Outcome::Draw => counts[1] += 1,
+                Outcome::Loss(_) => counts[2] += 1,
+            }
+        }
+
+        // counts are for white to move, after reduction by symmetry
+        println!(
+            "{:<8} {:>10} {:>10} {:>10} {:>10} {:>8}",
+            table.material().to_string(),
+            counts.iter().sum::<usize>(),
+            counts[0],
+            counts[1],
+            counts[2],
+            longest
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    time::Instant,
+};
+
+use psce_core::Position;
+use psce_search::EvalParams;
+
+struct Entry {
+    features: Vec<(usize, i32)>,
+    result: f64,
+}
+
+pub fn run(file: File, output: String, iterations: usize) {
+    let entries = parse_file(file);
+    let mut params = EvalParams::default();
+
+    println!(
+        "Tuning {} parameters on {} positions",
+        EvalParams::LEN,
+        entries.len()
+    );
+
+    let start = Instant::now();
+
+    let mut scores: Vec<_> = entries.iter().map(|e| score(&params, e)).collect();
+
+    let k = fit_k(&entries, &scores);
+    println!("Fitted K = {:.4}", k);
+
+    let occurrences = occurrences(&entries);
+    let mut error = total_error(&entries, &scores, k);
+
+    for iteration in 1..=iterations {
+        let mut improved = false;
+
+        for (index, occurrences) in occurrences.iter().enumerate() {
+            if occurrences.is_empty() {
+                continue;
+            }
+
+            for delta in [1, -1] {
+                let new_error = error + error_change(&entries, &scores, occurrences, delta, k);
+
+                if new_error < error {
+                    params.set(index, params.get(index) + delta);
+
+                    for &(e, coefficient) in occurrences {
+                        scores[e] += coefficient * delta;
+                    }
+
+                    error = new_error;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+
+        println!(
+            "Iteration {}: error = {:.6} ({}s)",
+            iteration,
+            error / entries.len() as f64,
+            start.elapsed().as_secs()
+        );
+
+        let mut f = File::create(&output).expect("Failed to create output file");
+        write!(f, "{}", params).expect("Failed to write parameters");
+
+        if !improved {
+            break;
+        }
+    }
+
+    println!("Wrote tuned parameters to {}", output);
+}
+
+fn score(params: &EvalParams, entry: &Entry) -> i32 {
+    entry
+        .features
+        .iter()
+        .map(|&(index, coefficient)| coefficient * params.get(index))
+        .sum()
+}
+
+fn sigmoid(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+fn total_error(entries: &[Entry], scores: &[i32], k: f64) -> f64 {
+    entries
+        .iter()
+        .zip(scores)
+        .map(|(e, &s)| (e.result - sigmoid(s, k)).powi(2))
+        .sum()
+}
+
+fn error_change(
+    entries: &[Entry],
+    scores: &[i32],
+    occurrences: &[(usize, i32)],
+    delta: i32,
+    k: f64,
+) -> f64 {
+    occurrences
+        .iter()
+        .map(|&(e, coefficient)| {
+            let old = scores[e];
+            let new = old + coefficient * delta;
+            let result = entries[e].result;
+
+            (result - sigmoid(new, k)).powi(2) - (result - sigmoid(old, k)).powi(2)
+        })
+        .sum()
+}
+
+fn fit_k(entries: &[Entry], scores: &[i32]) -> f64 {
+    let (mut lo, mut hi) = (0.0, 4.0);
+
+    for _ in 0..50 {
+        let m1 = lo + (hi - lo) / 3.0;
+        let m2 = hi - (hi - lo) / 3.0;
+
+        if total_error(entries, scores, m1) < total_error(entries, scores, m2) {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+
+    (lo + hi) / 2.0
+}
+
+fn occurrences(entries: &[Entry]) -> Vec<Vec<(usize, i32)>> {
+    let mut occurrences = vec![Vec::new(); EvalParams::LEN];
+
+    for (e, entry) in entries.iter().enumerate() {
+        let mut coefficients = vec![0; EvalParams::LEN];
+
+        for &(index, coefficient) in &entry.features {
+            coefficients[index] += coefficient;
+        }
+
+        for (index, coefficient) in coefficients.into_iter().enumerate() {
+            if coefficient != 0 {
+                occurrences[index].push((e, coefficient));
+            }
+        }
+    }
+
+    occurrences
+}
+
+fn parse_file(file: File) -> Vec<Entry> {
+    BufReader::new(file)
+        .lines()
+        .map(|l| l.expect("Failed to read line"))
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| parse_line(&l))
+        .collect()
+}
+
+fn parse_line(line: &str) -> Entry {
+    let fields: Vec<_> = line.split_whitespace().collect();
+    let fen = fields[..4].join(" ");
+    let position = Position::from_fen(&fen).expect("Invalid FEN");
+
+    let result = fields[4..]
+        .iter()
+        .find_map(|f| parse_result(f))
+        .expect("Missing result");
+
+    Entry {
+        features: EvalParams::features(&position),
+        result,
+    }
+}
+
+fn parse_result(field: &str) -> Option<f64> {
+    match field.trim_matches(|c| matches!(c, '"' | ';' | '[' | ']')) {
+        "1-0" | "1.0" => Some(1.0),
+        "0-1" | "0.0" => Some(0.0),
+        "1/2-1/2" | "0.5" => Some(0.5),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use psce_search::evaluate_position;
+
+    #[test]
+    fn test_features_match_eval() {
+        let entry = parse_line(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - c9 \"1/2-1/2\";",
+        );
+        let position = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        assert_eq!(entry.result, 0.5);
+        assert_eq!(
+            score(&EvalParams::default(), &entry),
+            evaluate_position(&position)
+        );
+    }
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(parse_result("[1.0]"), Some(1.0));
+        assert_eq!(parse_result("\"0-1\";"), Some(0.0));
+        assert_eq!(parse_result("c9"), None);
+    }
+}
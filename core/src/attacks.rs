@@ -0,0 +1,92 @@
+use crate::{
+    bitboard::{Bitboard, BitboardContainer},
+    color::Color,
+    piece::Piece,
+    square::Square,
+};
+
+// Attack detection by walking the board. Move generation has faster tables,
+// but core only needs this for the few variant rules it applies itself.
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+fn step(square: Square, (ranks, files): (i8, i8)) -> Option<Square> {
+    square.offset(ranks, files)
+}
+
+/// Squares a king on `square` could step to.
+pub(crate) fn neighbours(square: Square) -> impl Iterator<Item = Square> {
+    KING_STEPS.into_iter().filter_map(move |s| step(square, s))
+}
+
+/// Squares a `piece` of `color` on `square` attacks, with sliders stopped
+/// by the first square of `occupied` on their way.
+pub(crate) fn piece_attacks(
+    occupied: Bitboard,
+    color: Color,
+    piece: Piece,
+    square: Square,
+) -> Bitboard {
+    let mut attacks = Bitboard::empty();
+
+    let (steps, slides): (&[(i8, i8)], bool) = match piece {
+        Piece::Pawn => match color {
+            Color::White => (&[(1, -1), (1, 1)], false),
+            Color::Black => (&[(-1, -1), (-1, 1)], false),
+        },
+        Piece::Knight => (&KNIGHT_STEPS, false),
+        Piece::Bishop => (&BISHOP_DIRECTIONS, true),
+        Piece::Rook => (&ROOK_DIRECTIONS, true),
+        Piece::Queen => (&KING_STEPS, true),
+        Piece::King => (&KING_STEPS, false),
+    };
+
+    for &direction in steps {
+        let mut current = square;
+
+        while let Some(sq) = step(current, direction) {
+            attacks.set(sq);
+
+            if !slides || occupied.get(sq) {
+                break;
+            }
+
+            current = sq;
+        }
+    }
+
+    attacks
+}
+
+pub(crate) fn is_attacked(bitboards: &BitboardContainer, square: Square, by: Color) -> bool {
+    // a piece on `square` attacks the squares it is attacked from, except
+    // that pawns attack the other way
+    Piece::ALL.into_iter().any(|piece| {
+        (piece_attacks(bitboards.all(), !by, piece, square) & bitboards.piece(by, piece))
+            .is_not_empty()
+    })
+}
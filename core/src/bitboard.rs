@@ -1,6 +1,6 @@
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Shl, Shr};
 
-use crate::{color::Color, piece::Piece};
+use crate::{color::Color, piece::Piece, square::Square};
 
 /*
     a   b   c   d   e   f   g   h
@@ -39,20 +39,16 @@ impl Bitboard {
         self.0
     }
 
-    pub fn all_squares() -> BitboardAllSquaresIterator {
-        BitboardAllSquaresIterator::default()
+    pub fn get(&self, sq: Square) -> bool {
+        self.0 & sq.to_bb().0 != 0
     }
 
-    pub fn get(&self, sq: u8) -> bool {
-        self.0 & (1u64 << sq) != 0
+    pub fn set(&mut self, sq: Square) {
+        self.0 |= sq.to_bb().0;
     }
 
-    pub fn set(&mut self, sq: u8) {
-        self.0 |= 1u64 << sq;
-    }
-
-    pub fn clear(&mut self, sq: u8) {
-        self.0 &= !(1u64 << sq);
+    pub fn clear(&mut self, sq: Square) {
+        self.0 &= !sq.to_bb().0;
     }
 
     pub fn is_empty(&self) -> bool {
@@ -63,15 +59,15 @@ impl Bitboard {
         !self.is_empty()
     }
 
-    pub fn last_square(&self) -> Option<u8> {
+    pub fn last_square(&self) -> Option<Square> {
         if self.is_empty() {
             None
         } else {
-            Some(self.0.trailing_zeros() as u8)
+            Some(Square::from_index(self.0.trailing_zeros() as u8))
         }
     }
 
-    pub fn pop_square(&mut self) -> Option<u8> {
+    pub fn pop_square(&mut self) -> Option<Square> {
         let sq = self.last_square()?;
         self.clear(sq);
 
@@ -237,24 +233,6 @@ impl BitboardContainer {
     }
 }
 
-#[derive(Default)]
-pub struct BitboardAllSquaresIterator {
-    index: u8,
-}
-
-impl Iterator for BitboardAllSquaresIterator {
-    type Item = u8;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= 64 {
-            None
-        } else {
-            let sq = self.index;
-            self.index += 1;
-            Some(sq)
-        }
-    }
-}
 pub struct BitboardSetSquaresIterator {
     bitboard: Bitboard,
 }
@@ -266,7 +244,7 @@ impl BitboardSetSquaresIterator {
 }
 
 impl Iterator for BitboardSetSquaresIterator {
-    type Item = u8;
+    type Item = Square;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.bitboard.pop_square()
@@ -1,7 +1,10 @@
-use std::{fmt::Display, str::FromStr};
+use std::fmt::Display;
 
-use crate::{Bitboard, Square};
+use crate::{Color, File, Move, Rank, Square};
 
+/// Castling rights, one bit per side. The squares of the rooks they refer
+/// to are kept by the position, so rooks may start on any file as in
+/// Chess960.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Castling(u8);
 
@@ -15,19 +18,17 @@ impl Castling {
     pub const NONE: Self = Castling(0b0000);
     pub const ALL: Self = Castling(0b1111);
 
-    pub const WHITE_KING_SIDE_SQUARES: Bitboard =
-        Square::to_bb(Square::F1).bitor(Square::to_bb(Square::G1));
+    /// The single rights, in the order of `index`.
+    pub const SIDES: [Self; 4] = [
+        Self::WHITE_KING_SIDE,
+        Self::WHITE_QUEEN_SIDE,
+        Self::BLACK_KING_SIDE,
+        Self::BLACK_QUEEN_SIDE,
+    ];
 
-    pub const WHITE_QUEEN_SIDE_SQUARES: Bitboard = Square::to_bb(Square::D1)
-        .bitor(Square::to_bb(Square::C1))
-        .bitor(Square::to_bb(Square::B1));
-
-    pub const BLACK_KING_SIDE_SQUARES: Bitboard =
-        Square::to_bb(Square::F8).bitor(Square::to_bb(Square::G8));
-
-    pub const BLACK_QUEEN_SIDE_SQUARES: Bitboard = Square::to_bb(Square::D8)
-        .bitor(Square::to_bb(Square::C8))
-        .bitor(Square::to_bb(Square::B8));
+    pub const fn to_repr(self) -> u8 {
+        self.0
+    }
 
     pub fn can(self, cstl: Self) -> bool {
         (self.0 & cstl.0) != 0
@@ -40,29 +41,69 @@ impl Castling {
     pub fn clear(&mut self, cstl: Self) {
         self.0 &= !cstl.0;
     }
-}
 
-impl FromStr for Castling {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut cstl = Self::NONE;
-
-        if s == "-" {
-            return Ok(cstl);
+    pub fn side(color: Color, king_side: bool) -> Self {
+        match (color, king_side) {
+            (Color::White, true) => Self::WHITE_KING_SIDE,
+            (Color::White, false) => Self::WHITE_QUEEN_SIDE,
+            (Color::Black, true) => Self::BLACK_KING_SIDE,
+            (Color::Black, false) => Self::BLACK_QUEEN_SIDE,
         }
+    }
 
-        for c in s.chars() {
-            match c {
-                'K' => cstl.set(Self::WHITE_KING_SIDE),
-                'Q' => cstl.set(Self::WHITE_QUEEN_SIDE),
-                'k' => cstl.set(Self::BLACK_KING_SIDE),
-                'q' => cstl.set(Self::BLACK_QUEEN_SIDE),
-                _ => return Err(format!("Invalid castling rights: {}", s)),
-            }
+    pub fn all(color: Color) -> Self {
+        match color {
+            Color::White => Self::WHITE_ALL,
+            Color::Black => Self::BLACK_ALL,
         }
+    }
+
+    /// The side of a castling move, which is encoded as the king capturing
+    /// its own rook.
+    pub fn of_move(mv: &Move) -> Self {
+        let color = if mv.from().rank() == Rank::First {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        Self::side(color, mv.to() > mv.from())
+    }
+
+    /// Index of a single right in `SIDES`.
+    pub fn index(self) -> usize {
+        debug_assert_eq!(self.0.count_ones(), 1);
+
+        self.0.trailing_zeros() as usize
+    }
+
+    pub fn is_king_side(self) -> bool {
+        self.can(Self::WHITE_KING_SIDE) || self.can(Self::BLACK_KING_SIDE)
+    }
+
+    /// Square the king of a single right ends up on, the g or c file.
+    pub fn king_destination(self) -> Square {
+        self.destination(File::G, File::C)
+    }
+
+    /// Square the rook of a single right ends up on, the f or d file.
+    pub fn rook_destination(self) -> Square {
+        self.destination(File::F, File::D)
+    }
+
+    fn destination(self, king_side_file: File, queen_side_file: File) -> Square {
+        let rank = if self.can(Self::WHITE_ALL) {
+            Rank::First
+        } else {
+            Rank::Eighth
+        };
+        let file = if self.is_king_side() {
+            king_side_file
+        } else {
+            queen_side_file
+        };
 
-        Ok(cstl)
+        Square::from_rf(rank, file)
     }
 }
 
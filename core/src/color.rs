@@ -1,6 +1,6 @@
 use std::ops::Not;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Color {
     White,
     Black,
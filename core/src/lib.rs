@@ -1,3 +1,4 @@
+mod attacks;
 mod bitboard;
 mod castling;
 mod color;
@@ -6,11 +7,14 @@ mod piece;
 mod position;
 mod square;
 mod undo;
+mod variant;
+mod zobrist;
 
 pub use bitboard::Bitboard;
 pub use castling::Castling;
 pub use color::Color;
-pub use moves::{Move, MoveFlags};
+pub use moves::{Move, MoveFlags, PackedMove, UciMoveError};
 pub use piece::Piece;
 pub use position::Position;
-pub use square::Square;
+pub use square::{File, Rank, Square};
+pub use variant::Variant;
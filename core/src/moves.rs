@@ -1,18 +1,24 @@
-use std::{fmt::Display, ops::BitOr};
+use std::{error::Error, fmt::Display, ops::BitOr};
 
-use crate::{Color, Square, piece::Piece};
+use crate::{Castling, Color, Position, Square, piece::Piece};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Move {
-    from: u8,
-    to: u8,
+    from: Square,
+    to: Square,
     piece: Piece,
     promotion: Option<Piece>,
     flags: MoveFlags,
 }
 
 impl Move {
-    pub fn new(from: u8, to: u8, piece: Piece, promotion: Option<Piece>, flags: MoveFlags) -> Self {
+    pub fn new(
+        from: Square,
+        to: Square,
+        piece: Piece,
+        promotion: Option<Piece>,
+        flags: MoveFlags,
+    ) -> Self {
         Self {
             from,
             to,
@@ -22,11 +28,16 @@ impl Move {
         }
     }
 
-    pub fn from(&self) -> u8 {
+    /// Drops a piece from the crazyhouse pocket on `square`.
+    pub fn new_drop(piece: Piece, square: Square) -> Self {
+        Self::new(square, square, piece, None, MoveFlags::DROP)
+    }
+
+    pub fn from(&self) -> Square {
         self.from
     }
 
-    pub fn to(&self) -> u8 {
+    pub fn to(&self) -> Square {
         self.to
     }
 
@@ -41,15 +52,167 @@ impl Move {
     pub fn flags(&self) -> MoveFlags {
         self.flags
     }
-}
 
-impl Display for Move {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
+    pub fn is_promotion(&self) -> bool {
+        self.promotion.is_some()
+    }
+
+    /// A promotion to anything but a queen.
+    pub fn is_underpromotion(&self) -> bool {
+        self.promotion.is_some_and(|piece| piece != Piece::Queen)
+    }
+
+    /// Captures and promotions, which change the material on the board.
+    pub fn is_tactical(&self) -> bool {
+        self.flags.is_capture() || self.is_promotion()
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        !self.is_tactical()
+    }
+
+    pub fn pack(&self) -> PackedMove {
+        let special = if self.flags.is_drop() {
+            PackedMove::DROP + self.piece as u16
+        } else if self.flags.is_castling() {
+            PackedMove::CASTLING
+        } else if self.flags.is_en_passant() {
+            PackedMove::EN_PASSANT
+        } else {
+            self.promotion
+                .map_or(PackedMove::NORMAL, |piece| piece as u16)
+        };
+
+        PackedMove::from_parts(self.from, self.to, special)
+    }
+
+    /// Reads a move in UCI notation and checks it is legal in `position`.
+    /// Castling is accepted as the king taking its own rook, as Chess960
+    /// writes it, and outside Chess960 also as the king moving two squares.
+    pub fn from_uci(position: &Position, uci: &str) -> Result<Self, UciMoveError> {
+        let malformed = || UciMoveError::Malformed(uci.to_string());
+        let illegal = || UciMoveError::Illegal(uci.to_string());
+
+        let (packed, ambiguous) = if let Some((piece, square)) = uci.split_once('@') {
+            let mut chars = piece.chars();
+            let piece = match (chars.next(), chars.next()) {
+                (Some(c), None) => Piece::from_char(c).ok_or_else(malformed)?,
+                _ => return Err(malformed()),
+            };
+            let square = square.parse().map_err(|_| malformed())?;
+
+            (Self::new_drop(piece, square).pack(), false)
+        } else {
+            if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+                return Err(malformed());
+            }
+
+            let from = uci[..2].parse().map_err(|_| malformed())?;
+            let to = uci[2..4].parse().map_err(|_| malformed())?;
+            let promotion = match uci[4..].chars().next() {
+                Some(c) => Some(
+                    Piece::from_char(c)
+                        .filter(|&piece| piece != Piece::Pawn)
+                        .ok_or_else(malformed)?,
+                ),
+                None => None,
+            };
+
+            let (to, special) = Self::uci_special(position, from, to, promotion);
+            let queen = PackedMove::from_parts(from, to, Piece::Queen as u16);
+
+            // without a piece the move is only ambiguous if promoting is legal
+            let ambiguous = promotion.is_none()
+                && position.is_pseudo_legal(queen)
+                && queen
+                    .unpack(position)
+                    .is_some_and(|mv| position.is_legal(&mv));
+
+            (PackedMove::from_parts(from, to, special), ambiguous)
+        };
+
+        if ambiguous {
+            return Err(UciMoveError::AmbiguousPromotion(uci.to_string()));
+        }
+
+        let mv = Some(packed)
+            .filter(|&packed| position.is_pseudo_legal(packed))
+            .and_then(|packed| packed.unpack(position))
+            .ok_or_else(illegal)?;
+
+        if !position.is_legal(&mv) {
+            return Err(illegal());
+        }
+
+        Ok(mv)
+    }
+
+    /// The target square and packed special code of a move written from
+    /// `from` to `to`, which for castling is the rook's square.
+    fn uci_special(
+        position: &Position,
+        from: Square,
+        to: Square,
+        promotion: Option<Piece>,
+    ) -> (Square, u16) {
+        if let Some(promotion) = promotion {
+            return (to, promotion as u16);
+        }
+
+        let color = position.side_to_move();
+
+        match position.piece_at(from) {
+            Some((c, Piece::King)) if c == color => {
+                if position.piece_at(to) == Some((color, Piece::Rook)) {
+                    return (to, PackedMove::CASTLING);
+                }
+
+                // the king moving two squares is never an ordinary move
+                let rook = Castling::SIDES
+                    .into_iter()
+                    .filter(|&side| Castling::all(color).can(side))
+                    .find(|&side| {
+                        !position.is_chess960()
+                            && side.king_destination() == to
+                            && from.rank() == to.rank()
+                            && from.file().distance(to.file()) == 2
+                    })
+                    .and_then(|side| position.castling_rook(side));
+
+                match rook {
+                    Some(rook) => (rook, PackedMove::CASTLING),
+                    None => (to, PackedMove::NORMAL),
+                }
+            }
+            Some((c, Piece::Pawn))
+                if c == color
+                    && position.en_passant_square() == Some(to)
+                    && from.file() != to.file() =>
+            {
+                (to, PackedMove::EN_PASSANT)
+            }
+            _ => (to, PackedMove::NORMAL),
+        }
+    }
+
+    /// Long algebraic notation as used by UCI. Castling is stored as the
+    /// king capturing its own rook, which is how Chess960 writes it, and is
+    /// otherwise written as the king moving two squares.
+    pub fn to_uci(&self, chess960: bool) -> String {
+        if self.flags.is_drop() {
+            return format!("{}@{}", self.piece.to_char(Color::White), self.to);
+        }
+
+        let to = if self.flags.is_castling() && !chess960 {
+            Castling::of_move(self).king_destination()
+        } else {
+            self.to
+        };
+
+        format!(
             "{}{}{}",
-            Square::to_str(self.from),
-            Square::to_str(self.to),
+            self.from,
+            to,
             if let Some(promotion) = self.promotion {
                 format!("{}", promotion.to_char(Color::Black))
             } else {
@@ -59,6 +222,121 @@ impl Display for Move {
     }
 }
 
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_uci(false))
+    }
+}
+
+/// A move in 16 bits: the from and to squares and a special code in the top
+/// four bits. The rest of the move is recovered from the position.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PackedMove(u16);
+
+impl PackedMove {
+    // codes 1 to 5 are promotions, to the piece of that index
+    const NORMAL: u16 = 0;
+    const CASTLING: u16 = 6;
+    const EN_PASSANT: u16 = 7;
+    // plus the index of the dropped piece
+    const DROP: u16 = 8;
+
+    pub const fn new(repr: u16) -> Self {
+        Self(repr)
+    }
+
+    fn from_parts(from: Square, to: Square, special: u16) -> Self {
+        Self(from.index() as u16 | (to.index() as u16) << 6 | special << 12)
+    }
+
+    pub const fn to_repr(self) -> u16 {
+        self.0
+    }
+
+    pub fn from(self) -> Square {
+        Square::from_index((self.0 & 0x3f) as u8)
+    }
+
+    pub fn to(self) -> Square {
+        Square::from_index((self.0 >> 6 & 0x3f) as u8)
+    }
+
+    /// The full move in `position`, or `None` if the side to move has no
+    /// piece that could make it. The move is not checked to be legal, see
+    /// `Position::is_pseudo_legal`.
+    pub fn unpack(self, position: &Position) -> Option<Move> {
+        let (from, to) = (self.from(), self.to());
+        let special = self.0 >> 12;
+
+        if special >= Self::DROP {
+            let piece = Piece::ALL[..Piece::King as usize]
+                .get((special - Self::DROP) as usize)
+                .copied()?;
+
+            return (from == to).then(|| Move::new_drop(piece, to));
+        }
+
+        let (color, piece) = position.piece_at(from)?;
+        if color != position.side_to_move() {
+            return None;
+        }
+
+        let flags = if position.bitboards().all().get(to) {
+            MoveFlags::CAPTURE
+        } else {
+            MoveFlags::NONE
+        };
+
+        match (special, piece) {
+            (Self::NORMAL, Piece::Pawn) if from.rank().distance(to.rank()) == 2 => {
+                Some(Move::new(from, to, piece, None, MoveFlags::DOUBLE_PUSH))
+            }
+            (Self::NORMAL, _) => Some(Move::new(from, to, piece, None, flags)),
+            (Self::CASTLING, Piece::King) => {
+                Some(Move::new(from, to, piece, None, MoveFlags::CASTLING))
+            }
+            (Self::EN_PASSANT, Piece::Pawn) => Some(Move::new(
+                from,
+                to,
+                piece,
+                None,
+                MoveFlags::CAPTURE | MoveFlags::EN_PASSANT,
+            )),
+            (promotion @ 1..=5, Piece::Pawn) => Some(Move::new(
+                from,
+                to,
+                piece,
+                Some(Piece::ALL[promotion as usize]),
+                flags,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Why a move in UCI notation could not be read.
+#[derive(Clone, PartialEq, Debug)]
+pub enum UciMoveError {
+    /// Not written as a move at all.
+    Malformed(String),
+    /// A move that cannot be played in the position.
+    Illegal(String),
+    /// A pawn reaching the last rank without saying what it promotes to.
+    AmbiguousPromotion(String),
+}
+
+impl Display for UciMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(uci) => write!(f, "malformed move: {}", uci),
+            Self::Illegal(uci) => write!(f, "illegal move: {}", uci),
+            Self::AmbiguousPromotion(uci) => write!(f, "missing promotion piece: {}", uci),
+        }
+    }
+}
+
+impl Error for UciMoveError {}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct MoveFlags(u8);
 
@@ -68,6 +346,7 @@ impl MoveFlags {
     pub const EN_PASSANT: Self = MoveFlags(0b0010);
     pub const CASTLING: Self = MoveFlags(0b0100);
     pub const DOUBLE_PUSH: Self = MoveFlags(0b1000);
+    pub const DROP: Self = MoveFlags(0b10000);
 
     pub fn has(&self, flags: MoveFlags) -> bool {
         (self.0 & flags.0) != 0
@@ -88,6 +367,10 @@ impl MoveFlags {
     pub fn is_double_push(&self) -> bool {
         self.has(MoveFlags::DOUBLE_PUSH)
     }
+
+    pub fn is_drop(&self) -> bool {
+        self.has(MoveFlags::DROP)
+    }
 }
 
 impl BitOr<MoveFlags> for MoveFlags {
@@ -97,3 +380,117 @@ impl BitOr<MoveFlags> for MoveFlags {
         MoveFlags(self.0 | rhs.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let quiet = Move::new(Square::G1, Square::F3, Piece::Knight, None, MoveFlags::NONE);
+        assert!(quiet.is_quiet() && !quiet.is_tactical());
+
+        let capture = Move::new(
+            Square::E4,
+            Square::D5,
+            Piece::Pawn,
+            None,
+            MoveFlags::CAPTURE,
+        );
+        assert!(capture.is_tactical() && !capture.is_promotion());
+
+        let promotion = |piece| {
+            Move::new(
+                Square::A7,
+                Square::A8,
+                Piece::Pawn,
+                Some(piece),
+                MoveFlags::NONE,
+            )
+        };
+        assert!(promotion(Piece::Queen).is_tactical());
+        assert!(promotion(Piece::Queen).is_promotion());
+        assert!(!promotion(Piece::Queen).is_underpromotion());
+        assert!(promotion(Piece::Knight).is_underpromotion());
+    }
+
+    #[test]
+    fn test_from_uci() {
+        let position = Position::initial();
+        let mv = Move::from_uci(&position, "e2e4").unwrap();
+        assert_eq!(mv.flags(), MoveFlags::DOUBLE_PUSH);
+        assert_eq!(mv.to(), Square::E4);
+
+        let error = |fen, uci| Move::from_uci(&Position::from_fen(fen).unwrap(), uci).unwrap_err();
+        let fen = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1";
+
+        assert_eq!(error(fen, "e5"), UciMoveError::Malformed("e5".to_string()));
+        assert_eq!(
+            error(fen, "e5e9"),
+            UciMoveError::Malformed("e5e9".to_string())
+        );
+        assert_eq!(
+            error(fen, "e5e6p"),
+            UciMoveError::Malformed("e5e6p".to_string())
+        );
+        assert_eq!(
+            error(fen, "e5e7"),
+            UciMoveError::Illegal("e5e7".to_string())
+        );
+        assert_eq!(
+            error(fen, "b7a8"),
+            UciMoveError::AmbiguousPromotion("b7a8".to_string())
+        );
+        // a pinned pawn may not promote at all
+        assert_eq!(
+            error("8/KP5r/8/4k3/8/8/8/8 w - - 0 1", "b7b8"),
+            UciMoveError::Illegal("b7b8".to_string())
+        );
+        // the king may not step into check
+        assert_eq!(
+            error("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1", "e1d2").to_string(),
+            "illegal move: e1d2"
+        );
+
+        let position = Position::from_fen(fen).unwrap();
+        let promotion = Move::from_uci(&position, "b7a8n").unwrap();
+        assert_eq!(promotion.promotion(), Some(Piece::Knight));
+        assert!(promotion.flags().is_capture());
+
+        let en_passant = Move::from_uci(&position, "e5d6").unwrap();
+        assert!(en_passant.flags().is_en_passant());
+
+        for uci in ["e1g1", "e1h1"] {
+            let castling = Move::from_uci(&position, uci).unwrap();
+            assert!(castling.flags().is_castling());
+            assert_eq!(castling.to(), Square::H1);
+        }
+
+        // Chess960 only writes castling as the king taking the rook
+        let fen = "1r2k1r1/1p4p1/8/8/8/8/1P4P1/1R2K1R1 w GBgb - 0 1";
+        let position = Position::from_fen(fen).unwrap();
+        assert!(
+            Move::from_uci(&position, "e1g1")
+                .unwrap()
+                .flags()
+                .is_castling()
+        );
+        assert!(
+            !Move::from_uci(&position, "e1f1")
+                .unwrap()
+                .flags()
+                .is_castling()
+        );
+        assert_eq!(
+            Move::from_uci(&position, "e1c1").unwrap_err(),
+            UciMoveError::Illegal("e1c1".to_string())
+        );
+
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1").unwrap();
+        assert_eq!(
+            Move::from_uci(&position, "N@e4").unwrap(),
+            Move::new_drop(Piece::Knight, Square::E4)
+        );
+        assert!(Move::from_uci(&position, "Q@e4").is_err());
+    }
+}
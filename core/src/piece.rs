@@ -1,6 +1,6 @@
 use crate::color::Color;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Piece {
     Pawn,
     Knight,
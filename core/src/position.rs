@@ -1,32 +1,71 @@
 use std::fmt::Display;
 
 use crate::{
-    bitboard::BitboardContainer, castling::Castling, color::Color, moves::Move, piece::Piece,
-    square::Square, undo::Undo,
+    attacks,
+    bitboard::{Bitboard, BitboardContainer},
+    castling::Castling,
+    color::Color,
+    moves::{Move, PackedMove},
+    piece::Piece,
+    square::{File, Rank, Square},
+    undo::Undo,
+    variant::Variant,
+    zobrist,
 };
 
 pub const FEN_INITIAL_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
+/// Checks that win a three-check game.
+const MAX_CHECKS: u8 = 3;
+
+/// The squares a king wins on in King of the Hill.
+const HILL: Bitboard = Bitboard::new(0x0000_0018_1800_0000);
+
+/// Pieces that can be held in a crazyhouse pocket, in FEN order.
+const POCKET_PIECES: [Piece; 5] = [
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
 #[derive(Clone)]
 pub struct Position {
     bitboards: BitboardContainer,
+    /// What stands on each square, kept alongside the bitboards.
+    mailbox: [Option<(Color, Piece)>; 64],
     side_to_move: Color,
     castling: Castling,
-    en_passant_square: Option<u8>,
+    castling_rooks: [Square; 4],
+    chess960: bool,
+    variant: Variant,
+    checks: [u8; 2],
+    pockets: [[u8; 5]; 2],
+    promoted: Bitboard,
+    en_passant_square: Option<Square>,
     halfmove_clock: u8,
     fullmove_number: u16,
-    // TODO: hash
+    hash: u64,
 }
 
 impl Position {
     pub fn empty() -> Self {
         Self {
             bitboards: BitboardContainer::empty(),
+            mailbox: [None; 64],
             side_to_move: Color::White,
             castling: Castling::NONE,
+            castling_rooks: [Square::H1, Square::A1, Square::H8, Square::A8],
+            chess960: false,
+            variant: Variant::Standard,
+            checks: [0; 2],
+            pockets: [[0; 5]; 2],
+            promoted: Bitboard::empty(),
             en_passant_square: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            hash: 0,
         }
     }
 
@@ -43,22 +82,76 @@ impl Position {
             return Err("FEN string too short".to_string());
         }
 
-        let rows: Vec<_> = parts[0].split('/').collect();
+        // crazyhouse pockets follow the board in brackets or as a ninth row
+        let (board, pocket) = match parts[0].split_once('[') {
+            Some((board, pocket)) => (
+                board,
+                Some(
+                    pocket
+                        .strip_suffix(']')
+                        .ok_or("Invalid pocket".to_string())?,
+                ),
+            ),
+            None => match parts[0].matches('/').count() {
+                8 => parts[0]
+                    .rsplit_once('/')
+                    .map_or((parts[0], None), |(board, pocket)| (board, Some(pocket))),
+                _ => (parts[0], None),
+            },
+        };
+
+        if let Some(pocket) = pocket {
+            position.variant = Variant::Crazyhouse;
+
+            for c in pocket.chars().filter(|&c| c != '-') {
+                let piece = Piece::from_char(c)
+                    .filter(|&piece| piece != Piece::King)
+                    .ok_or("Invalid pocket piece".to_string())?;
+                let color = if c.is_lowercase() {
+                    Color::Black
+                } else {
+                    Color::White
+                };
+
+                let count = &mut position.pockets[color as usize][piece as usize];
+                if *count >= 16 {
+                    return Err("Too many pocket pieces".to_string());
+                }
+
+                *count += 1;
+            }
+        }
+
+        let rows: Vec<_> = board.split('/').collect();
 
         if rows.len() != 8 {
             return Err("Invalid number of rows".to_string());
         }
 
         for (y, row) in rows.iter().enumerate() {
-            let mut x = 0;
+            let rank = Rank::ALL[7 - y];
+            let mut x = 0u8;
 
             for c in row.chars() {
+                // a promoted piece, which turns back into a pawn when captured
+                if c == '~' {
+                    let occupied = position.bitboards.color(Color::White)
+                        | position.bitboards.color(Color::Black);
+
+                    match File::new(x.wrapping_sub(1)).map(|file| Square::from_rf(rank, file)) {
+                        Some(square) if occupied.get(square) => position.promoted.set(square),
+                        _ => return Err(format!("Invalid promoted marker in row {}", y)),
+                    }
+
+                    continue;
+                }
+
                 if x >= 8 {
                     return Err(format!("Invalid length of row {}", y));
                 }
 
                 if let Some(n) = c.to_digit(10) {
-                    x += n as usize;
+                    x += n as u8;
                 } else {
                     let color = if c.is_lowercase() {
                         Color::Black
@@ -68,10 +161,9 @@ impl Position {
 
                     let piece = Piece::from_char(c).ok_or("Invalid piece".to_string())?;
 
-                    let square = Square::from_rf(7 - (y as u8), x as u8);
+                    let square = Square::from_rf(rank, File::ALL[x as usize]);
 
-                    position.bitboards.piece_mut(color, piece).set(square);
-                    position.bitboards.color_mut(color).set(square);
+                    position.put_piece(color, piece, square);
 
                     x += 1;
                 }
@@ -91,32 +183,264 @@ impl Position {
             _ => return Err("Invalid side to move".to_string()),
         };
 
-        position.castling = parts[2].parse()?;
+        position.parse_castling(parts[2])?;
 
         position.en_passant_square = match parts[3] {
             "-" => None,
-            s => Some(Square::from_str(s).ok_or("Invalid en passant square".to_string())?),
+            s => Some(
+                s.parse()
+                    .map_err(|_| "Invalid en passant square".to_string())?,
+            ),
         };
 
-        position.halfmove_clock = if parts.len() > 4 {
-            parts[4]
+        // three-check counters come as remaining checks (`3+3`) before the
+        // clocks or as checks given (`+0+0`) after them
+        let mut clocks = Vec::new();
+
+        for &part in &parts[4..] {
+            let Some((white, black)) = part.split_once('+') else {
+                clocks.push(part);
+                continue;
+            };
+
+            let given = white.is_empty();
+            let (white, black) = match black.split_once('+') {
+                Some((white, black)) if given => (white, black),
+                _ => (white, black),
+            };
+
+            let mut checks = [0; 2];
+            for (color, count) in [(Color::White, white), (Color::Black, black)] {
+                let count: u8 = count
+                    .parse()
+                    .ok()
+                    .filter(|&count| count <= MAX_CHECKS)
+                    .ok_or("Invalid check counter".to_string())?;
+
+                checks[color as usize] = if given { count } else { MAX_CHECKS - count };
+            }
+
+            position.variant = Variant::ThreeCheck;
+            position.checks = checks;
+        }
+
+        position.halfmove_clock = match clocks.first() {
+            Some(clock) => clock
                 .parse()
-                .map_err(|_| "Invalid halfmove clock".to_string())?
-        } else {
-            0
+                .map_err(|_| "Invalid halfmove clock".to_string())?,
+            None => 0,
         };
 
-        position.fullmove_number = if parts.len() > 5 {
-            parts[5]
+        position.fullmove_number = match clocks.get(1) {
+            Some(number) => number
                 .parse()
-                .map_err(|_| "Invalid fullmove number".to_string())?
-        } else {
-            1
+                .map_err(|_| "Invalid fullmove number".to_string())?,
+            None => 1,
         };
 
+        position.hash = position.compute_hash();
+
         Ok(position)
     }
 
+    /// Reads the castling field as standard `KQkq`, X-FEN (`KQkq` naming the
+    /// outermost rooks, file letters for inner ones) or Shredder-FEN (file
+    /// letters only).
+    fn parse_castling(&mut self, field: &str) -> Result<(), String> {
+        if field == "-" {
+            return Ok(());
+        }
+
+        let error = || format!("Invalid castling rights: {}", field);
+
+        for c in field.chars() {
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            let rank = match color {
+                Color::White => Rank::First,
+                Color::Black => Rank::Eighth,
+            };
+
+            let king = self
+                .king_square(color)
+                .filter(|&king| king.rank() == rank)
+                .ok_or_else(error)?;
+            let king_file = king.file();
+            let rooks = self.bitboards.piece(color, Piece::Rook);
+            let has_rook = |file| rooks.get(Square::from_rf(rank, file));
+
+            let rook_file = match c.to_ascii_lowercase() {
+                'k' => File::ALL
+                    .into_iter()
+                    .rev()
+                    .find(|&file| file > king_file && has_rook(file)),
+                'q' => File::ALL
+                    .into_iter()
+                    .find(|&file| file < king_file && has_rook(file)),
+                file => {
+                    self.chess960 = true;
+                    file.to_string().parse().ok()
+                }
+            }
+            .filter(|&file| file != king_file && has_rook(file))
+            .ok_or_else(error)?;
+
+            let side = Castling::side(color, rook_file > king_file);
+            self.castling.set(side);
+            self.castling_rooks[side.index()] = Square::from_rf(rank, rook_file);
+
+            // only the standard setup can be written as plain KQkq
+            let standard_rook = if side.is_king_side() {
+                File::H
+            } else {
+                File::A
+            };
+            if king_file != File::E || rook_file != standard_rook {
+                self.chess960 = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn castling_fen(&self) -> String {
+        if !self.chess960 || self.castling == Castling::NONE {
+            return self.castling.to_string();
+        }
+
+        Castling::SIDES
+            .into_iter()
+            .filter_map(|side| {
+                let rook = self.castling_rook(side)?;
+                let c = rook.file().to_char();
+
+                Some(if rook.rank() == Rank::First {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                })
+            })
+            .collect()
+    }
+
+    /// Builds a position from `(color, piece, square)` triples, without
+    /// castling rights or en passant square.
+    pub fn from_pieces(pieces: &[(Color, Piece, Square)], side_to_move: Color) -> Self {
+        let mut position = Self::empty();
+
+        for &(color, piece, square) in pieces {
+            position.put_piece(color, piece, square);
+            position.bitboards.all_mut().set(square);
+        }
+
+        position.side_to_move = side_to_move;
+        position.hash = position.compute_hash();
+
+        position
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut rows = Vec::new();
+
+        for rank in Rank::ALL.into_iter().rev() {
+            let mut row = String::new();
+            let mut empty = 0;
+
+            for file in File::ALL {
+                match self.piece_at(Square::from_rf(rank, file)) {
+                    Some((color, piece)) => {
+                        if empty > 0 {
+                            row.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+
+                        row.push(piece.to_char(color));
+
+                        if self.variant == Variant::Crazyhouse
+                            && self.promoted.get(Square::from_rf(rank, file))
+                        {
+                            row.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+
+            rows.push(row);
+        }
+
+        let mut board = rows.join("/");
+
+        if self.variant == Variant::Crazyhouse {
+            board.push('[');
+
+            for color in Color::ALL {
+                for piece in POCKET_PIECES {
+                    for _ in 0..self.pocket(color, piece) {
+                        board.push(piece.to_char(color));
+                    }
+                }
+            }
+
+            board.push(']');
+        }
+
+        let mut en_passant = self
+            .en_passant_square
+            .map_or("-".to_string(), |square| square.to_string());
+
+        if self.variant == Variant::ThreeCheck {
+            en_passant.push_str(&format!(
+                " {}+{}",
+                MAX_CHECKS - self.checks[Color::White as usize],
+                MAX_CHECKS - self.checks[Color::Black as usize]
+            ));
+        }
+
+        format!(
+            "{} {} {} {} {} {}",
+            board,
+            match self.side_to_move {
+                Color::White => "w",
+                Color::Black => "b",
+            },
+            self.castling_fen(),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    fn compute_hash(&self) -> u64 {
+        let mut hash = zobrist::castling(self.castling)
+            ^ zobrist::en_passant(self.en_passant_square)
+            ^ zobrist::side_to_move(self.side_to_move);
+
+        for color in Color::ALL {
+            for piece in Piece::ALL {
+                for square in self.bitboards.piece(color, piece).squares() {
+                    hash ^= zobrist::piece(color, piece, square);
+                }
+            }
+
+            for piece in POCKET_PIECES {
+                hash ^= zobrist::pocket(color, piece, self.pocket(color, piece));
+            }
+
+            hash ^= zobrist::checks(color, self.checks[color as usize]);
+        }
+
+        hash
+    }
+
     pub fn bitboards(&self) -> &BitboardContainer {
         &self.bitboards
     }
@@ -129,7 +453,80 @@ impl Position {
         self.castling
     }
 
-    pub fn en_passant_square(&self) -> Option<u8> {
+    /// Starting square of the rook a single castling right refers to, if
+    /// that right is still available.
+    pub fn castling_rook(&self, side: Castling) -> Option<Square> {
+        self.castling
+            .can(side)
+            .then(|| self.castling_rooks[side.index()])
+    }
+
+    /// Whether castling is written king-takes-rook in UCI and Shredder
+    /// letters in FEN.
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+
+        if variant == Variant::Antichess {
+            self.castling = Castling::NONE;
+        }
+
+        self.hash = self.compute_hash();
+    }
+
+    /// Checks `color` has given in a three-check game.
+    pub fn checks(&self, color: Color) -> u8 {
+        self.checks[color as usize]
+    }
+
+    /// The side that has won by a rule of the variant, as opposed to by
+    /// checkmate. Positions won this way have no moves left.
+    pub fn variant_winner(&self) -> Option<Color> {
+        match self.variant {
+            Variant::ThreeCheck => Color::ALL
+                .into_iter()
+                .find(|&color| self.checks[color as usize] >= MAX_CHECKS),
+            Variant::KingOfTheHill => Color::ALL.into_iter().find(|&color| {
+                self.king_square(color)
+                    .is_some_and(|square| HILL.get(square))
+            }),
+            Variant::Atomic => Color::ALL
+                .into_iter()
+                .find(|&color| self.king_square(!color).is_none()),
+            // being left without a move wins as well, which takes a move
+            // generator to tell
+            Variant::Antichess => Color::ALL
+                .into_iter()
+                .find(|&color| self.bitboards.color(color).is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Number of `piece`s `color` holds in its crazyhouse pocket.
+    pub fn pocket(&self, color: Color, piece: Piece) -> u8 {
+        match piece {
+            Piece::King => 0,
+            _ => self.pockets[color as usize][piece as usize],
+        }
+    }
+
+    /// Pieces that were promoted from pawns, tracked for crazyhouse.
+    pub fn promoted(&self) -> Bitboard {
+        self.promoted
+    }
+
+    pub fn en_passant_square(&self) -> Option<Square> {
         self.en_passant_square
     }
 
@@ -141,105 +538,130 @@ impl Position {
         self.fullmove_number
     }
 
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
     pub fn make_move(&mut self, mv: &Move) -> Undo {
         let mut undo = Undo {
             captured_piece: None,
             previous_castling: self.castling,
+            previous_promoted: self.promoted,
+            previous_checks: self.checks,
+            exploded: Vec::new(),
             previous_en_passant_square: self.en_passant_square,
             previous_halfmove_clock: self.halfmove_clock,
+            previous_hash: self.hash,
         };
 
         let color = self.side_to_move;
         let opponent = !color;
 
-        self.bitboards.piece_mut(color, mv.piece()).clear(mv.from());
-        self.bitboards.color_mut(color).clear(mv.from());
+        self.hash ^= zobrist::castling(self.castling) ^ zobrist::en_passant(self.en_passant_square);
 
-        if let Some(promoted) = mv.promotion() {
-            self.bitboards.piece_mut(color, promoted).set(mv.to());
-        } else {
-            self.bitboards.piece_mut(color, mv.piece()).set(mv.to());
-        }
-        self.bitboards.color_mut(color).set(mv.to());
+        let captured_promoted = self.promoted.get(mv.to());
+        let moved_promoted = self.promoted.get(mv.from()) || mv.is_promotion();
+        self.promoted.clear(mv.from());
+        self.promoted.clear(mv.to());
 
+        // the captured piece leaves first, so the capturing one can take
+        // its place
         if mv.flags().is_capture() {
-            if mv.flags().is_en_passant() {
-                let square = match color {
-                    Color::White => mv.to() - 8,
-                    Color::Black => mv.to() + 8,
-                };
-
-                undo.captured_piece = Some(Piece::Pawn);
-                self.bitboards
-                    .piece_mut(opponent, Piece::Pawn)
-                    .clear(square);
-                self.bitboards.color_mut(opponent).clear(square);
+            let square = if mv.flags().is_en_passant() {
+                Square::from_rf(mv.from().rank(), mv.to().file())
             } else {
-                for piece in Piece::ALL {
-                    if self.bitboards.piece(opponent, piece).get(mv.to()) {
-                        undo.captured_piece = Some(piece);
-                        self.bitboards.piece_mut(opponent, piece).clear(mv.to());
-                        break;
-                    }
-                }
-                self.bitboards.color_mut(opponent).clear(mv.to());
-
-                let castling_mask = match mv.to() {
-                    Square::A1 => Castling::WHITE_QUEEN_SIDE,
-                    Square::H1 => Castling::WHITE_KING_SIDE,
-                    Square::A8 => Castling::BLACK_QUEEN_SIDE,
-                    Square::H8 => Castling::BLACK_KING_SIDE,
-                    _ => Castling::NONE,
+                mv.to()
+            };
+
+            if let Some((_, captured)) = self.piece_at(square) {
+                undo.captured_piece = Some(captured);
+                self.remove_piece(opponent, captured, square);
+                self.hash ^= zobrist::piece(opponent, captured, square);
+            }
+
+            // captures change sides, promoted pieces as the pawns they were
+            if let Some(captured) = undo.captured_piece
+                && self.variant == Variant::Crazyhouse
+            {
+                let piece = if captured_promoted {
+                    Piece::Pawn
+                } else {
+                    captured
                 };
 
-                self.castling.clear(castling_mask);
+                self.set_pocket(color, piece, self.pocket(color, piece) + 1);
             }
         }
 
+        if mv.flags().is_castling() {
+            let side = Castling::of_move(mv);
+            let (king_to, rook_to) = (side.king_destination(), side.rook_destination());
+
+            // both leave first, as in Chess960 they may land on each other's square
+            for (piece, from) in [(Piece::King, mv.from()), (Piece::Rook, mv.to())] {
+                self.remove_piece(color, piece, from);
+                self.hash ^= zobrist::piece(color, piece, from);
+            }
+
+            for (piece, to) in [(Piece::King, king_to), (Piece::Rook, rook_to)] {
+                self.put_piece(color, piece, to);
+                self.hash ^= zobrist::piece(color, piece, to);
+            }
+        } else if mv.flags().is_drop() {
+            self.set_pocket(color, mv.piece(), self.pocket(color, mv.piece()) - 1);
+
+            self.put_piece(color, mv.piece(), mv.to());
+            self.hash ^= zobrist::piece(color, mv.piece(), mv.to());
+        } else {
+            if moved_promoted {
+                self.promoted.set(mv.to());
+            }
+
+            self.remove_piece(color, mv.piece(), mv.from());
+            self.hash ^= zobrist::piece(color, mv.piece(), mv.from());
+
+            let placed = mv.promotion().unwrap_or(mv.piece());
+            self.put_piece(color, placed, mv.to());
+            self.hash ^= zobrist::piece(color, placed, mv.to());
+        }
+
+        if mv.flags().is_capture() && self.variant == Variant::Atomic {
+            self.explode(mv.to(), &mut undo.exploded);
+        }
+
         if mv.flags().is_double_push() {
             self.en_passant_square = match color {
-                Color::White => Some(mv.to() - 8),
-                Color::Black => Some(mv.to() + 8),
+                Color::White => mv.to().south(),
+                Color::Black => mv.to().north(),
             };
         } else {
             self.en_passant_square = None;
         }
 
-        if mv.flags().is_castling() {
-            let (rook_from, rook_to) = match mv.to() {
-                Square::C1 => (Square::A1, Square::D1),
-                Square::G1 => (Square::H1, Square::F1),
-                Square::C8 => (Square::A8, Square::D8),
-                Square::G8 => (Square::H8, Square::F8),
-                _ => unreachable!(),
-            };
-
-            self.bitboards
-                .piece_mut(color, Piece::Rook)
-                .clear(rook_from);
-            self.bitboards.color_mut(color).clear(rook_from);
-
-            self.bitboards.piece_mut(color, Piece::Rook).set(rook_to);
-            self.bitboards.color_mut(color).set(rook_to);
+        if mv.piece() == Piece::King {
+            self.castling.clear(Castling::all(color));
+        }
 
-            let castling_mask = match color {
-                Color::White => Castling::WHITE_ALL,
-                Color::Black => Castling::BLACK_ALL,
-            };
+        // atomic captures can take the enemy king or blow up either one
+        if undo.captured_piece == Some(Piece::King) {
+            self.castling.clear(Castling::all(opponent));
+        }
 
-            self.castling.clear(castling_mask);
-        } else {
-            let castling_mask = match mv.from() {
-                Square::A1 => Castling::WHITE_QUEEN_SIDE,
-                Square::E1 => Castling::WHITE_ALL,
-                Square::H1 => Castling::WHITE_KING_SIDE,
-                Square::A8 => Castling::BLACK_QUEEN_SIDE,
-                Square::E8 => Castling::BLACK_ALL,
-                Square::H8 => Castling::BLACK_KING_SIDE,
-                _ => Castling::NONE,
-            };
+        for &(exploded, piece, _) in &undo.exploded {
+            if piece == Piece::King {
+                self.castling.clear(Castling::all(exploded));
+            }
+        }
 
-            self.castling.clear(castling_mask);
+        // a right is lost once its rook moves or is captured
+        for side in Castling::SIDES {
+            if self.castling_rook(side).is_some_and(|rook| {
+                rook == mv.from()
+                    || rook == mv.to()
+                    || undo.exploded.iter().any(|&(_, _, square)| square == rook)
+            }) {
+                self.castling.clear(side);
+            }
         }
 
         *self.bitboards.all_mut() =
@@ -255,73 +677,202 @@ impl Position {
             self.fullmove_number += 1;
         }
 
+        if self.variant == Variant::ThreeCheck
+            && let Some(king) = self.king_square(opponent)
+            && attacks::is_attacked(&self.bitboards, king, color)
+        {
+            let checks = self.checks[color as usize];
+
+            self.hash ^= zobrist::checks(color, checks) ^ zobrist::checks(color, checks + 1);
+            self.checks[color as usize] = checks + 1;
+        }
+
         self.side_to_move = opponent;
 
+        self.hash ^= zobrist::castling(self.castling)
+            ^ zobrist::en_passant(self.en_passant_square)
+            ^ zobrist::side_to_move(Color::Black);
+
+        debug_assert!(self.is_mailbox_consistent());
+
         undo
     }
 
+    /// Removes the capturing piece on `square` and every piece but pawns
+    /// around it.
+    fn explode(&mut self, square: Square, exploded: &mut Vec<(Color, Piece, Square)>) {
+        for sq in std::iter::once(square).chain(attacks::neighbours(square)) {
+            let Some((color, piece)) = self.piece_at(sq) else {
+                continue;
+            };
+
+            if piece == Piece::Pawn && sq != square {
+                continue;
+            }
+
+            self.remove_piece(color, piece, sq);
+            self.promoted.clear(sq);
+            self.hash ^= zobrist::piece(color, piece, sq);
+
+            exploded.push((color, piece, sq));
+        }
+    }
+
+    fn put_piece(&mut self, color: Color, piece: Piece, square: Square) {
+        self.bitboards.piece_mut(color, piece).set(square);
+        self.bitboards.color_mut(color).set(square);
+        self.mailbox[square.index()] = Some((color, piece));
+    }
+
+    fn remove_piece(&mut self, color: Color, piece: Piece, square: Square) {
+        self.bitboards.piece_mut(color, piece).clear(square);
+        self.bitboards.color_mut(color).clear(square);
+        self.mailbox[square.index()] = None;
+    }
+
+    /// Whether the mailbox agrees with the bitboards.
+    fn is_mailbox_consistent(&self) -> bool {
+        let mut mailbox = [None; 64];
+
+        for color in Color::ALL {
+            for piece in Piece::ALL {
+                for square in self.bitboards.piece(color, piece).squares() {
+                    mailbox[square.index()] = Some((color, piece));
+                }
+            }
+        }
+
+        self.mailbox == mailbox
+    }
+
+    fn set_pocket(&mut self, color: Color, piece: Piece, count: u8) {
+        let pocket = &mut self.pockets[color as usize][piece as usize];
+
+        self.hash ^= zobrist::pocket(color, piece, *pocket) ^ zobrist::pocket(color, piece, count);
+        *pocket = count;
+    }
+
     pub fn undo_move(&mut self, mv: &Move, undo: &Undo) {
         self.side_to_move = !self.side_to_move;
         let color = self.side_to_move;
         let opponent = !color;
 
         self.castling = undo.previous_castling;
+        self.promoted = undo.previous_promoted;
+        self.checks = undo.previous_checks;
+
+        for &(color, piece, square) in &undo.exploded {
+            self.put_piece(color, piece, square);
+        }
         self.en_passant_square = undo.previous_en_passant_square;
         self.halfmove_clock = undo.previous_halfmove_clock;
+        self.hash = undo.previous_hash;
 
         if self.side_to_move == Color::Black {
             self.fullmove_number -= 1;
         }
 
-        let current_piece_on_to_sq = mv.promotion().unwrap_or(mv.piece());
-        self.bitboards
-            .piece_mut(color, current_piece_on_to_sq)
-            .clear(mv.to());
-        self.bitboards.color_mut(color).clear(mv.to());
-
-        self.bitboards.piece_mut(color, mv.piece()).set(mv.from());
-        self.bitboards.color_mut(color).set(mv.from());
-
         if mv.flags().is_castling() {
-            let (rook_from, rook_to) = match mv.to() {
-                Square::C1 => (Square::A1, Square::D1),
-                Square::G1 => (Square::H1, Square::F1),
-                Square::C8 => (Square::A8, Square::D8),
-                Square::G8 => (Square::H8, Square::F8),
-                _ => unreachable!(),
-            };
+            let side = Castling::of_move(mv);
 
-            self.bitboards.piece_mut(color, Piece::Rook).clear(rook_to);
-            self.bitboards.color_mut(color).clear(rook_to);
+            for (piece, to) in [
+                (Piece::King, side.king_destination()),
+                (Piece::Rook, side.rook_destination()),
+            ] {
+                self.remove_piece(color, piece, to);
+            }
 
-            self.bitboards.piece_mut(color, Piece::Rook).set(rook_from);
-            self.bitboards.color_mut(color).set(rook_from);
+            for (piece, from) in [(Piece::King, mv.from()), (Piece::Rook, mv.to())] {
+                self.put_piece(color, piece, from);
+            }
+        } else if mv.flags().is_drop() {
+            self.remove_piece(color, mv.piece(), mv.to());
+            self.pockets[color as usize][mv.piece() as usize] += 1;
+        } else {
+            self.remove_piece(color, mv.promotion().unwrap_or(mv.piece()), mv.to());
+            self.put_piece(color, mv.piece(), mv.from());
         }
 
         if mv.flags().is_capture() {
-            if mv.flags().is_en_passant() {
-                let square = match color {
-                    Color::White => mv.to() - 8,
-                    Color::Black => mv.to() + 8,
-                };
-
-                self.bitboards.piece_mut(opponent, Piece::Pawn).set(square);
-                self.bitboards.color_mut(opponent).set(square);
+            let square = if mv.flags().is_en_passant() {
+                Square::from_rf(mv.from().rank(), mv.to().file())
             } else {
-                let captured_piece = undo.captured_piece.unwrap();
+                mv.to()
+            };
+
+            if let Some(captured) = undo.captured_piece {
+                self.put_piece(opponent, captured, square);
+            }
+
+            if let Some(captured) = undo.captured_piece
+                && self.variant == Variant::Crazyhouse
+            {
+                let piece = if undo.previous_promoted.get(mv.to()) {
+                    Piece::Pawn
+                } else {
+                    captured
+                };
 
-                self.bitboards
-                    .piece_mut(opponent, captured_piece)
-                    .set(mv.to());
-                self.bitboards.color_mut(opponent).set(mv.to());
+                self.pockets[color as usize][piece as usize] -= 1;
             }
         }
 
         *self.bitboards.all_mut() =
             self.bitboards.color(Color::White) | self.bitboards.color(Color::Black);
+
+        debug_assert!(self.is_mailbox_consistent());
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<(Color, Piece)> {
+        self.mailbox[square.index()]
     }
 
-    pub fn king_square(&self, color: Color) -> Option<u8> {
+    /// Passes the turn without moving a piece, as used by null move pruning.
+    /// Passing out of check is never allowed.
+    pub fn make_null_move(&mut self) -> Undo {
+        debug_assert!(
+            !self.is_in_check(self.side_to_move),
+            "null move while in check"
+        );
+
+        let undo = Undo {
+            captured_piece: None,
+            previous_castling: self.castling,
+            previous_promoted: self.promoted,
+            previous_checks: self.checks,
+            exploded: Vec::new(),
+            previous_en_passant_square: self.en_passant_square,
+            previous_halfmove_clock: self.halfmove_clock,
+            previous_hash: self.hash,
+        };
+
+        self.hash ^=
+            zobrist::en_passant(self.en_passant_square) ^ zobrist::side_to_move(Color::Black);
+        self.en_passant_square = None;
+        self.halfmove_clock += 1;
+
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.side_to_move = !self.side_to_move;
+
+        undo
+    }
+
+    pub fn undo_null_move(&mut self, undo: &Undo) {
+        self.side_to_move = !self.side_to_move;
+
+        self.en_passant_square = undo.previous_en_passant_square;
+        self.halfmove_clock = undo.previous_halfmove_clock;
+        self.hash = undo.previous_hash;
+
+        if self.side_to_move == Color::Black {
+            self.fullmove_number -= 1;
+        }
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
         self.bitboards.piece(color, Piece::King).last_square()
     }
 
@@ -329,21 +880,205 @@ impl Position {
         if mv.flags().is_en_passant() {
             Some(Piece::Pawn)
         } else if mv.flags().is_capture() {
-            for piece in Piece::ALL {
-                if self
-                    .bitboards
-                    .piece(!self.side_to_move(), piece)
-                    .get(mv.to())
-                {
-                    return Some(piece);
-                }
+            self.piece_at(mv.to()).map(|(_, piece)| piece)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `packed` is a move the side to move could make here, as
+    /// generated by the move generator. Moves from the transposition table
+    /// may belong to another position with the same hash and are checked
+    /// with this before being played. Legality is left to the caller.
+    pub fn is_pseudo_legal(&self, packed: PackedMove) -> bool {
+        let Some(mv) = packed.unpack(self) else {
+            return false;
+        };
+
+        if self.variant_winner().is_some() {
+            return false;
+        }
+
+        let color = self.side_to_move;
+        let occupied = self.bitboards.all();
+        let to = mv.to();
+
+        if mv.flags().is_drop() {
+            return self.variant == Variant::Crazyhouse
+                && self.pocket(color, mv.piece()) > 0
+                && !occupied.get(to)
+                && !(mv.piece() == Piece::Pawn && matches!(to.rank(), Rank::First | Rank::Eighth));
+        }
+
+        if mv.flags().is_castling() {
+            let side = Castling::of_move(&mv);
+
+            return self.king_square(color) == Some(mv.from())
+                && self.castling_rook(side) == Some(to)
+                && self.can_castle(side);
+        }
+
+        if self.bitboards.color(color).get(to) {
+            return false;
+        }
+
+        let moves = match mv.piece() {
+            Piece::Pawn => self.is_pawn_move(&mv),
+            // an atomic king would explode itself by capturing
+            Piece::King if self.variant == Variant::Atomic && mv.flags().is_capture() => false,
+            piece => attacks::piece_attacks(occupied, color, piece, mv.from()).get(to),
+        };
+
+        moves
+            && (self.variant != Variant::Antichess
+                || mv.flags().is_capture()
+                || !self.has_capture())
+    }
+
+    /// Whether `mv` leaves the mover's king safe, by the rules of the
+    /// variant. Move generation has a faster version for the search.
+    pub(crate) fn is_legal(&self, mv: &Move) -> bool {
+        let color = self.side_to_move;
+        let mut after = self.clone();
+        after.make_move(mv);
+
+        after.leaves_king_safe(color)
+    }
+
+    /// Whether the side to move may castle on `side` now: the right is
+    /// kept, everything the king and rook cross is empty but themselves and
+    /// the king crosses no attacked square.
+    pub fn can_castle(&self, side: Castling) -> bool {
+        let color = self.side_to_move;
+
+        let (Some(king), Some(rook)) = (self.king_square(color), self.castling_rook(side)) else {
+            return false;
+        };
+
+        if !Castling::all(color).can(side) {
+            return false;
+        }
+
+        let king_span = rank_span(king, side.king_destination());
+        let rook_span = rank_span(rook, side.rook_destination());
+
+        // everything the king and rook cross must be empty but themselves
+        let others = self.bitboards.all() & !(king.to_bb() | rook.to_bb());
+
+        (others & (king_span | rook_span)).is_empty()
+            && !king_span
+                .squares()
+                .any(|square| attacks::is_attacked(&self.bitboards, square, !color))
+    }
+
+    /// Whether the king of `color` could be captured if it were the other
+    /// side's turn, by the rules of the variant, as `MoveGen::is_in_check`
+    /// decides with its faster attack tables.
+    pub(crate) fn is_in_check(&self, color: Color) -> bool {
+        if self.variant == Variant::Antichess {
+            return false;
+        }
+
+        let Some(king) = self.king_square(color) else {
+            return false;
+        };
+
+        // touching kings cannot take each other without exploding
+        if self.variant == Variant::Atomic
+            && self
+                .king_square(!color)
+                .is_some_and(|other| attacks::neighbours(king).any(|sq| sq == other))
+        {
+            return false;
+        }
+
+        attacks::is_attacked(&self.bitboards, king, !color)
+    }
+
+    /// Whether `color`, which just moved to reach this position, did so
+    /// without leaving its king in check.
+    pub(crate) fn leaves_king_safe(&self, color: Color) -> bool {
+        match self.variant {
+            // exploding the other king wins even when ours is attacked, but
+            // ours must survive
+            Variant::Atomic => {
+                self.king_square(color).is_some()
+                    && (self.king_square(!color).is_none() || !self.is_in_check(color))
             }
+            _ => !self.is_in_check(color),
+        }
+    }
 
-            None
+    fn is_pawn_move(&self, mv: &Move) -> bool {
+        let color = self.side_to_move;
+        let occupied = self.bitboards.all();
+        let (from, to) = (mv.from(), mv.to());
+
+        let (forward, start_rank, promotion_rank) = match color {
+            Color::White => (1, Rank::Second, Rank::Eighth),
+            Color::Black => (-1, Rank::Seventh, Rank::First),
+        };
+
+        let promotes = to.rank() == promotion_rank;
+        let promotion_allowed = match mv.promotion() {
+            None => !promotes,
+            Some(Piece::King) => promotes && self.variant == Variant::Antichess,
+            Some(_) => promotes,
+        };
+
+        if !promotion_allowed {
+            return false;
+        }
+
+        let attacks = attacks::piece_attacks(occupied, color, Piece::Pawn, from);
+
+        if mv.flags().is_en_passant() {
+            return self.en_passant_square == Some(to) && attacks.get(to);
+        }
+
+        if mv.flags().is_capture() {
+            return attacks.get(to);
+        }
+
+        let Some(single) = from.offset(forward, 0).filter(|&sq| !occupied.get(sq)) else {
+            return false;
+        };
+
+        if mv.flags().is_double_push() {
+            from.rank() == start_rank && single.offset(forward, 0) == Some(to) && !occupied.get(to)
         } else {
-            None
+            single == to
         }
     }
+
+    /// Whether the side to move has any capture, which antichess forces.
+    fn has_capture(&self) -> bool {
+        let color = self.side_to_move;
+        let occupied = self.bitboards.all();
+        let targets = self.bitboards.color(!color);
+
+        Piece::ALL.into_iter().any(|piece| {
+            let targets = match (piece, self.en_passant_square) {
+                (Piece::Pawn, Some(square)) => targets | square.to_bb(),
+                _ => targets,
+            };
+
+            self.bitboards.piece(color, piece).squares().any(|square| {
+                (attacks::piece_attacks(occupied, color, piece, square) & targets).is_not_empty()
+            })
+        })
+    }
+}
+
+/// Squares from `a` to `b` on the same rank, both included.
+fn rank_span(a: Square, b: Square) -> Bitboard {
+    let mut span = Bitboard::empty();
+
+    for square in Square::all().filter(|&square| square >= a.min(b) && square <= a.max(b)) {
+        span.set(square);
+    }
+
+    span
 }
 
 impl Display for Position {
@@ -351,27 +1086,17 @@ impl Display for Position {
         writeln!(f, "    a b c d e f g h")?;
         writeln!(f, "  +{}+", "-".repeat(17))?;
 
-        for rank in (0..8).rev() {
-            write!(f, "{} | ", rank + 1)?;
+        for rank in Rank::ALL.into_iter().rev() {
+            write!(f, "{} | ", rank)?;
 
-            'outer: for file in 0..8 {
-                for color in Color::ALL {
-                    for piece in Piece::ALL {
-                        if self
-                            .bitboards
-                            .piece(color, piece)
-                            .get(Square::from_rf(rank, file))
-                        {
-                            write!(f, "{} ", piece.to_char(color))?;
-                            continue 'outer;
-                        }
-                    }
+            for file in File::ALL {
+                match self.piece_at(Square::from_rf(rank, file)) {
+                    Some((color, piece)) => write!(f, "{} ", piece.to_char(color))?,
+                    None => write!(f, ". ")?,
                 }
-
-                write!(f, ". ")?;
             }
 
-            writeln!(f, "| {}", rank + 1)?;
+            writeln!(f, "| {}", rank)?;
         }
 
         writeln!(f, "  +{}+", "-".repeat(17))?;
@@ -384,8 +1109,7 @@ impl Display for Position {
             f,
             "En passant: {}",
             self.en_passant_square
-                .map(Square::to_str)
-                .unwrap_or("-".to_string())
+                .map_or("-".to_string(), |square| square.to_string())
         )?;
         writeln!(f, "Halfmove clock: {}", self.halfmove_clock)?;
         writeln!(f, "Fullmove number: {}", self.fullmove_number)?;
@@ -396,8 +1120,28 @@ impl Display for Position {
 
 #[cfg(test)]
 mod tests {
+    use crate::MoveFlags;
+
     use super::*;
 
+    #[test]
+    fn test_from_pieces() {
+        let position = Position::from_pieces(
+            &[
+                (Color::White, Piece::King, Square::E1),
+                (Color::Black, Piece::King, Square::E8),
+                (Color::White, Piece::Rook, Square::A1),
+            ],
+            Color::Black,
+        );
+
+        assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+        assert_eq!(
+            position.hash(),
+            Position::from_fen(&position.to_fen()).unwrap().hash()
+        );
+    }
+
     #[test]
     fn test_from_fen() {
         let position = Position::from_fen(FEN_INITIAL_POSITION).unwrap();
@@ -406,4 +1150,257 @@ mod tests {
         assert_eq!(position.castling, Castling::ALL);
         assert_eq!(position.en_passant_square, None);
     }
+
+    #[test]
+    fn test_to_fen() {
+        for fen in [
+            FEN_INITIAL_POSITION,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        ] {
+            assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_chess960_fen() {
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let position = Position::from_fen(fen).unwrap();
+
+        assert!(position.is_chess960());
+        assert_eq!(
+            position.castling_rook(Castling::WHITE_KING_SIDE),
+            Some(Square::H1)
+        );
+        assert_eq!(
+            position.castling_rook(Castling::BLACK_QUEEN_SIDE),
+            Some(Square::F8)
+        );
+        assert_eq!(position.to_fen(), fen);
+
+        // X-FEN names the outermost rooks with KQkq
+        let xfen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KFkf - 2 9";
+        assert_eq!(Position::from_fen(xfen).unwrap().to_fen(), fen);
+
+        let mut position = Position::initial();
+        assert!(!position.is_chess960());
+        position.set_chess960(true);
+        assert!(position.to_fen().contains(" HAha "));
+        assert_eq!(
+            Position::from_fen(&position.to_fen()).unwrap().castling(),
+            Castling::ALL
+        );
+
+        assert!(Position::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").is_err());
+    }
+
+    #[test]
+    fn test_crazyhouse() {
+        let fen = "r2q1k1Q~/8/8/8/8/8/8/4K3[RNPpp] b - - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+
+        assert_eq!(position.variant(), Variant::Crazyhouse);
+        assert_eq!(position.pocket(Color::Black, Piece::Pawn), 2);
+        assert!(position.promoted().get(Square::H8));
+        assert_eq!(position.to_fen(), fen);
+
+        // a captured promoted queen goes into the pocket as a pawn
+        let capture = Move::new(
+            Square::D8,
+            Square::H8,
+            Piece::Queen,
+            None,
+            MoveFlags::CAPTURE,
+        );
+        let drop = Move::new_drop(Piece::Rook, Square::E2);
+        let undos = [position.make_move(&capture), position.make_move(&drop)];
+
+        assert_eq!(
+            position.to_fen(),
+            "r4k1q/8/8/8/8/8/4R3/4K3[NPppp] b - - 1 2"
+        );
+        assert_eq!(
+            position.hash(),
+            Position::from_fen(&position.to_fen()).unwrap().hash()
+        );
+
+        for (mv, undo) in [capture, drop].iter().zip(undos).rev() {
+            position.undo_move(mv, &undo);
+        }
+
+        assert_eq!(position.to_fen(), fen);
+        assert_eq!(position.hash(), Position::from_fen(fen).unwrap().hash());
+    }
+
+    #[test]
+    fn test_three_check() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 2+3 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+
+        assert_eq!(position.variant(), Variant::ThreeCheck);
+        assert_eq!(position.checks(Color::White), 1);
+        assert_eq!(position.to_fen(), fen);
+
+        let given = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +1+0").unwrap();
+        assert_eq!(given.to_fen(), fen);
+
+        let check = Move::new(Square::A1, Square::A8, Piece::Rook, None, MoveFlags::NONE);
+        let undo = position.make_move(&check);
+
+        assert_eq!(position.checks(Color::White), 2);
+        assert_eq!(
+            position.hash(),
+            Position::from_fen(&position.to_fen()).unwrap().hash()
+        );
+
+        position.undo_move(&check, &undo);
+        assert_eq!(position.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_atomic_explosion() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3";
+        let mut position = Position::from_fen(fen).unwrap();
+        position.set_variant(Variant::Atomic);
+
+        // the knight takes e5 and explodes with it, d5 and the pawns survive
+        let capture = Move::new(
+            Square::F3,
+            Square::E5,
+            Piece::Knight,
+            None,
+            MoveFlags::CAPTURE,
+        );
+        let undo = position.make_move(&capture);
+
+        assert_eq!(
+            position.to_fen(),
+            "rnbqkbnr/ppp2ppp/8/3p4/4P3/8/PPPP1PPP/RNBQKB1R b KQkq - 0 3"
+        );
+        assert_eq!(undo.exploded.len(), 1);
+
+        position.undo_move(&capture, &undo);
+        assert_eq!(position.to_fen(), fen);
+
+        let fen = "4k3/8/8/8/8/8/3q4/R3K2R b KQ - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+        position.set_variant(Variant::Atomic);
+
+        let capture = Move::new(
+            Square::D2,
+            Square::E1,
+            Piece::Queen,
+            None,
+            MoveFlags::CAPTURE,
+        );
+        position.make_move(&capture);
+
+        assert_eq!(position.variant_winner(), Some(Color::Black));
+        assert_eq!(position.castling(), Castling::NONE);
+    }
+
+    #[test]
+    fn test_null_move() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+        let mut position = Position::from_fen(fen).unwrap();
+
+        let undo = position.make_null_move();
+        assert_eq!(position.side_to_move(), Color::Black);
+        assert_eq!(position.en_passant_square(), None);
+        assert_eq!(position.halfmove_clock(), 1);
+        assert_eq!(
+            position.hash(),
+            Position::from_fen(&position.to_fen()).unwrap().hash()
+        );
+
+        let again = position.make_null_move();
+        assert_eq!(position.fullmove_number(), 4);
+        position.undo_null_move(&again);
+
+        position.undo_null_move(&undo);
+        assert_eq!(position.to_fen(), fen);
+        assert_eq!(position.hash(), Position::from_fen(fen).unwrap().hash());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "null move while in check")]
+    fn test_null_move_in_check() {
+        let fen = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+        let mut position = Position::from_fen(fen).unwrap();
+
+        position.make_null_move();
+    }
+
+    #[test]
+    fn test_incremental_hash() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(fen).unwrap();
+
+        let moves = [
+            Move::new(
+                Square::A2,
+                Square::A4,
+                Piece::Pawn,
+                None,
+                MoveFlags::DOUBLE_PUSH,
+            ),
+            Move::new(
+                Square::B4,
+                Square::A3,
+                Piece::Pawn,
+                None,
+                MoveFlags::CAPTURE | MoveFlags::EN_PASSANT,
+            ),
+            Move::new(
+                Square::E1,
+                Square::H1,
+                Piece::King,
+                None,
+                MoveFlags::CASTLING,
+            ),
+            Move::new(
+                Square::A3,
+                Square::B2,
+                Piece::Pawn,
+                None,
+                MoveFlags::CAPTURE,
+            ),
+            Move::new(
+                Square::D5,
+                Square::E6,
+                Piece::Pawn,
+                None,
+                MoveFlags::CAPTURE,
+            ),
+            Move::new(
+                Square::B2,
+                Square::A1,
+                Piece::Pawn,
+                Some(Piece::Queen),
+                MoveFlags::CAPTURE,
+            ),
+        ];
+
+        let mut undos = Vec::new();
+
+        for mv in &moves {
+            undos.push(position.make_move(mv));
+
+            let expected = Position::from_fen(&position.to_fen()).unwrap();
+            assert_eq!(position.hash(), expected.hash(), "{}", mv);
+        }
+
+        let undo = position.make_null_move();
+        let expected = Position::from_fen(&position.to_fen()).unwrap();
+        assert_eq!(position.hash(), expected.hash());
+        position.undo_null_move(&undo);
+
+        for (mv, undo) in moves.iter().zip(undos).rev() {
+            position.undo_move(mv, &undo);
+        }
+
+        assert_eq!(position.hash(), Position::from_fen(fen).unwrap().hash());
+    }
 }
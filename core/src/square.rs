@@ -1,133 +1,351 @@
+use std::{fmt::Display, str::FromStr};
+
 use crate::Bitboard;
 
-pub struct Square {}
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+impl File {
+    pub const ALL: [Self; 8] = [
+        Self::A,
+        Self::B,
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+        Self::H,
+    ];
+
+    pub const fn new(index: u8) -> Option<Self> {
+        if index < 8 {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn offset(self, delta: i8) -> Option<Self> {
+        Self::new((self as u8).checked_add_signed(delta)?)
+    }
+
+    pub fn distance(self, other: Self) -> u8 {
+        (self as u8).abs_diff(other as u8)
+    }
+
+    /// The file on the other wing, `A` for `H`.
+    pub const fn flip(self) -> Self {
+        Self::ALL[7 - self as usize]
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self as u8) as char
+    }
+}
+
+impl FromStr for File {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[c @ b'a'..=b'h'] => Ok(Self::ALL[(c - b'a') as usize]),
+            _ => Err(format!("Invalid file: {}", s)),
+        }
+    }
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Rank {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    Eighth,
+}
+
+impl Rank {
+    pub const ALL: [Self; 8] = [
+        Self::First,
+        Self::Second,
+        Self::Third,
+        Self::Fourth,
+        Self::Fifth,
+        Self::Sixth,
+        Self::Seventh,
+        Self::Eighth,
+    ];
+
+    pub const fn new(index: u8) -> Option<Self> {
+        if index < 8 {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn offset(self, delta: i8) -> Option<Self> {
+        Self::new((self as u8).checked_add_signed(delta)?)
+    }
+
+    pub fn distance(self, other: Self) -> u8 {
+        (self as u8).abs_diff(other as u8)
+    }
+
+    /// The rank as seen from the other side, `Eighth` for `First`.
+    pub const fn flip(self) -> Self {
+        Self::ALL[7 - self as usize]
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self as u8) as char
+    }
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[c @ b'1'..=b'8'] => Ok(Self::ALL[(c - b'1') as usize]),
+            _ => Err(format!("Invalid rank: {}", s)),
+        }
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// A square of the board, numbered from a1 = 0 to h8 = 63 rank by rank.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Square(u8);
 
-#[allow(dead_code)]
 impl Square {
-    pub const A1: u8 = 0;
-    pub const B1: u8 = 1;
-    pub const C1: u8 = 2;
-    pub const D1: u8 = 3;
-    pub const E1: u8 = 4;
-    pub const F1: u8 = 5;
-    pub const G1: u8 = 6;
-    pub const H1: u8 = 7;
-
-    pub const A2: u8 = 8;
-    pub const B2: u8 = 9;
-    pub const C2: u8 = 10;
-    pub const D2: u8 = 11;
-    pub const E2: u8 = 12;
-    pub const F2: u8 = 13;
-    pub const G2: u8 = 14;
-    pub const H2: u8 = 15;
-
-    pub const A3: u8 = 16;
-    pub const B3: u8 = 17;
-    pub const C3: u8 = 18;
-    pub const D3: u8 = 19;
-    pub const E3: u8 = 20;
-    pub const F3: u8 = 21;
-    pub const G3: u8 = 22;
-    pub const H3: u8 = 23;
-
-    pub const A4: u8 = 24;
-    pub const B4: u8 = 25;
-    pub const C4: u8 = 26;
-    pub const D4: u8 = 27;
-    pub const E4: u8 = 28;
-    pub const F4: u8 = 29;
-    pub const G4: u8 = 30;
-    pub const H4: u8 = 31;
-
-    pub const A5: u8 = 32;
-    pub const B5: u8 = 33;
-    pub const C5: u8 = 34;
-    pub const D5: u8 = 35;
-    pub const E5: u8 = 36;
-    pub const F5: u8 = 37;
-    pub const G5: u8 = 38;
-    pub const H5: u8 = 39;
-
-    pub const A6: u8 = 40;
-    pub const B6: u8 = 41;
-    pub const C6: u8 = 42;
-    pub const D6: u8 = 43;
-    pub const E6: u8 = 44;
-    pub const F6: u8 = 45;
-    pub const G6: u8 = 46;
-    pub const H6: u8 = 47;
-
-    pub const A7: u8 = 48;
-    pub const B7: u8 = 49;
-    pub const C7: u8 = 50;
-    pub const D7: u8 = 51;
-    pub const E7: u8 = 52;
-    pub const F7: u8 = 53;
-    pub const G7: u8 = 54;
-    pub const H7: u8 = 55;
-
-    pub const A8: u8 = 56;
-    pub const B8: u8 = 57;
-    pub const C8: u8 = 58;
-    pub const D8: u8 = 59;
-    pub const E8: u8 = 60;
-    pub const F8: u8 = 61;
-    pub const G8: u8 = 62;
-    pub const H8: u8 = 63;
+    pub const A1: Self = Self(0);
+    pub const B1: Self = Self(1);
+    pub const C1: Self = Self(2);
+    pub const D1: Self = Self(3);
+    pub const E1: Self = Self(4);
+    pub const F1: Self = Self(5);
+    pub const G1: Self = Self(6);
+    pub const H1: Self = Self(7);
+
+    pub const A2: Self = Self(8);
+    pub const B2: Self = Self(9);
+    pub const C2: Self = Self(10);
+    pub const D2: Self = Self(11);
+    pub const E2: Self = Self(12);
+    pub const F2: Self = Self(13);
+    pub const G2: Self = Self(14);
+    pub const H2: Self = Self(15);
+
+    pub const A3: Self = Self(16);
+    pub const B3: Self = Self(17);
+    pub const C3: Self = Self(18);
+    pub const D3: Self = Self(19);
+    pub const E3: Self = Self(20);
+    pub const F3: Self = Self(21);
+    pub const G3: Self = Self(22);
+    pub const H3: Self = Self(23);
+
+    pub const A4: Self = Self(24);
+    pub const B4: Self = Self(25);
+    pub const C4: Self = Self(26);
+    pub const D4: Self = Self(27);
+    pub const E4: Self = Self(28);
+    pub const F4: Self = Self(29);
+    pub const G4: Self = Self(30);
+    pub const H4: Self = Self(31);
+
+    pub const A5: Self = Self(32);
+    pub const B5: Self = Self(33);
+    pub const C5: Self = Self(34);
+    pub const D5: Self = Self(35);
+    pub const E5: Self = Self(36);
+    pub const F5: Self = Self(37);
+    pub const G5: Self = Self(38);
+    pub const H5: Self = Self(39);
+
+    pub const A6: Self = Self(40);
+    pub const B6: Self = Self(41);
+    pub const C6: Self = Self(42);
+    pub const D6: Self = Self(43);
+    pub const E6: Self = Self(44);
+    pub const F6: Self = Self(45);
+    pub const G6: Self = Self(46);
+    pub const H6: Self = Self(47);
+
+    pub const A7: Self = Self(48);
+    pub const B7: Self = Self(49);
+    pub const C7: Self = Self(50);
+    pub const D7: Self = Self(51);
+    pub const E7: Self = Self(52);
+    pub const F7: Self = Self(53);
+    pub const G7: Self = Self(54);
+    pub const H7: Self = Self(55);
+
+    pub const A8: Self = Self(56);
+    pub const B8: Self = Self(57);
+    pub const C8: Self = Self(58);
+    pub const D8: Self = Self(59);
+    pub const E8: Self = Self(60);
+    pub const F8: Self = Self(61);
+    pub const G8: Self = Self(62);
+    pub const H8: Self = Self(63);
 }
 
 impl Square {
-    pub fn from_rf(rank: u8, file: u8) -> u8 {
-        (rank * 8) + file
+    pub const fn new(index: u8) -> Option<Self> {
+        if index < 64 { Some(Self(index)) } else { None }
     }
 
-    pub fn to_rf(sq: u8) -> (u8, u8) {
-        (sq / 8, sq % 8)
+    /// Callers guarantee `index < 64`, as bitboard bit positions are.
+    pub(crate) const fn from_index(index: u8) -> Self {
+        debug_assert!(index < 64);
+
+        Self(index)
     }
 
-    pub const fn to_bb(sq: u8) -> Bitboard {
-        Bitboard::new(1 << sq)
+    pub const fn from_rf(rank: Rank, file: File) -> Self {
+        Self(rank as u8 * 8 + file as u8)
     }
 
-    pub fn from_str(s: &str) -> Option<u8> {
-        let chars: Vec<_> = s.to_lowercase().chars().collect();
+    pub const fn rank(self) -> Rank {
+        Rank::ALL[(self.0 / 8) as usize]
+    }
 
-        if chars.len() != 2 {
-            return None;
-        }
+    pub const fn file(self) -> File {
+        File::ALL[(self.0 % 8) as usize]
+    }
 
-        let file = chars[0];
-        let rank = chars[1];
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
 
-        if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
-            return None;
-        }
+    pub const fn to_bb(self) -> Bitboard {
+        Bitboard::new(1 << self.0)
+    }
 
-        Some(Self::from_rf(rank as u8 - b'1', file as u8 - b'a'))
+    /// Every square from a1 to h8.
+    pub fn all() -> impl DoubleEndedIterator<Item = Self> {
+        (0..64).map(Self)
     }
 
-    pub fn to_str(sq: u8) -> String {
-        format!("{}{}", (sq % 8 + b'a') as char, (sq / 8 + b'1') as char)
+    /// The square `ranks` up and `files` to the right, if still on the board.
+    pub fn offset(self, ranks: i8, files: i8) -> Option<Self> {
+        Some(Self::from_rf(
+            self.rank().offset(ranks)?,
+            self.file().offset(files)?,
+        ))
     }
 
-    pub const fn north(sq: u8) -> u8 {
-        sq + 8
+    pub fn north(self) -> Option<Self> {
+        self.offset(1, 0)
     }
 
-    pub const fn east(sq: u8) -> u8 {
-        sq + 1
+    pub fn east(self) -> Option<Self> {
+        self.offset(0, 1)
     }
 
-    pub const fn south(sq: u8) -> u8 {
-        sq - 8
+    pub fn south(self) -> Option<Self> {
+        self.offset(-1, 0)
     }
 
-    pub const fn west(sq: u8) -> u8 {
-        sq - 1
+    pub fn west(self) -> Option<Self> {
+        self.offset(0, -1)
     }
 
-    pub const fn mirror(sq: u8) -> u8 {
-        sq ^ 0b111000
+    /// Number of king steps between the two squares.
+    pub fn distance(self, other: Self) -> u8 {
+        self.rank()
+            .distance(other.rank())
+            .max(self.file().distance(other.file()))
+    }
+
+    /// The square with the rank flipped, as seen from the other side.
+    pub const fn mirror(self) -> Self {
+        Self(self.0 ^ 0b111000)
+    }
+
+    /// The square with the file flipped, on the other wing.
+    pub const fn flip(self) -> Self {
+        Self(self.0 ^ 0b000111)
+    }
+}
+
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid square: {}", s);
+
+        if !s.is_ascii() || s.len() != 2 {
+            return Err(error());
+        }
+
+        let file = s[..1].parse().map_err(|_| error())?;
+        let rank = s[1..].parse().map_err(|_| error())?;
+
+        Ok(Self::from_rf(rank, file))
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        assert_eq!("e4".parse(), Ok(Square::E4));
+        assert!("e9".parse::<Square>().is_err());
+        assert!("E4".parse::<Square>().is_err());
+        assert_eq!(Square::E4.to_string(), "e4");
+
+        assert_eq!(Square::from_rf(Rank::Fourth, File::E), Square::E4);
+        assert_eq!(Square::E4.rank(), Rank::Fourth);
+        assert_eq!(Square::E4.file(), File::E);
+        assert_eq!(Square::new(64), None);
+        assert_eq!(Square::all().count(), 64);
+
+        assert_eq!(Square::E4.offset(2, -1), Some(Square::D6));
+        assert_eq!(Square::H4.east(), None);
+        assert_eq!(Square::A1.south(), None);
+        assert_eq!(Square::B1.distance(Square::G7), 6);
+        assert_eq!(Square::B1.mirror(), Square::B8);
+        assert_eq!(Square::B1.flip(), Square::G1);
     }
 }
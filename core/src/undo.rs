@@ -1,10 +1,14 @@
-use crate::{castling::Castling, piece::Piece};
+use crate::{bitboard::Bitboard, castling::Castling, color::Color, piece::Piece, square::Square};
 
 #[derive(Debug)]
 pub struct Undo {
     pub captured_piece: Option<Piece>,
     pub previous_castling: Castling,
-    pub previous_en_passant_square: Option<u8>,
+    pub previous_promoted: Bitboard,
+    pub previous_checks: [u8; 2],
+    /// Pieces removed by an atomic explosion, the capturer included.
+    pub exploded: Vec<(Color, Piece, Square)>,
+    pub previous_en_passant_square: Option<Square>,
     pub previous_halfmove_clock: u8,
-    // TODO: hash
+    pub previous_hash: u64,
 }
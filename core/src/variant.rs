@@ -0,0 +1,67 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Rules the position is played under.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Captured pieces change sides into the capturer's pocket and can be
+    /// dropped back on the board instead of moving.
+    Crazyhouse,
+    /// Giving the third check wins.
+    ThreeCheck,
+    /// Bringing the king to one of the four centre squares wins.
+    KingOfTheHill,
+    /// Captures explode every piece but pawns next to the capture square,
+    /// capturer included, and exploding the enemy king wins.
+    Atomic,
+    /// Captures are compulsory, the king is an ordinary piece and losing
+    /// every piece or having no move wins.
+    Antichess,
+}
+
+impl Variant {
+    pub const ALL: [Self; 6] = [
+        Self::Standard,
+        Self::Crazyhouse,
+        Self::ThreeCheck,
+        Self::KingOfTheHill,
+        Self::Atomic,
+        Self::Antichess,
+    ];
+
+    /// Name used by the `UCI_Variant` option.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Standard => "chess",
+            Self::Crazyhouse => "crazyhouse",
+            Self::ThreeCheck => "3check",
+            Self::KingOfTheHill => "kingofthehill",
+            Self::Atomic => "atomic",
+            Self::Antichess => "antichess",
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "standard" => Ok(Self::Standard),
+            "threecheck" | "three-check" => Ok(Self::ThreeCheck),
+            "koth" | "king-of-the-hill" => Ok(Self::KingOfTheHill),
+            "giveaway" => Ok(Self::Antichess),
+            name => Self::ALL
+                .into_iter()
+                .find(|variant| variant.name() == name)
+                .ok_or_else(|| format!("Unknown variant: {}", s)),
+        }
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
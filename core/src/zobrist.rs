@@ -0,0 +1,133 @@
+use crate::{castling::Castling, color::Color, piece::Piece, square::Square};
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+    (state, z ^ (z >> 31))
+}
+
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    castling: [u64; 16],
+    en_passant_files: [u64; 8],
+    side_to_move: u64,
+    pockets: [[[u64; POCKET_SIZE]; 5]; 2],
+    checks: [[u64; 3]; 2],
+}
+
+/// Most pieces of one kind a crazyhouse pocket can hold, all sixteen pawns.
+const POCKET_SIZE: usize = 16;
+
+const fn generate_keys() -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        castling: [0; 16],
+        en_passant_files: [0; 8],
+        side_to_move: 0,
+        pockets: [[[0; POCKET_SIZE]; 5]; 2],
+        checks: [[0; 3]; 2],
+    };
+
+    let mut state = 0x5053_4345;
+    let mut value;
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut square = 0;
+            while square < 64 {
+                (state, value) = splitmix64(state);
+                keys.pieces[color][piece][square] = value;
+                square += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+
+    let mut i = 0;
+    while i < 16 {
+        (state, value) = splitmix64(state);
+        keys.castling[i] = value;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        (state, value) = splitmix64(state);
+        keys.en_passant_files[i] = value;
+        i += 1;
+    }
+
+    (state, keys.side_to_move) = splitmix64(state);
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 5 {
+            let mut count = 0;
+            while count < POCKET_SIZE {
+                (state, value) = splitmix64(state);
+                keys.pockets[color][piece][count] = value;
+                count += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+
+    let mut color = 0;
+    while color < 2 {
+        let mut count = 0;
+        while count < 3 {
+            (state, value) = splitmix64(state);
+            keys.checks[color][count] = value;
+            count += 1;
+        }
+        color += 1;
+    }
+
+    keys
+}
+
+static KEYS: Keys = generate_keys();
+
+pub fn piece(color: Color, piece: Piece, square: Square) -> u64 {
+    KEYS.pieces[color as usize][piece as usize][square.index()]
+}
+
+pub fn castling(castling: Castling) -> u64 {
+    KEYS.castling[castling.to_repr() as usize]
+}
+
+pub fn en_passant(square: Option<Square>) -> u64 {
+    square.map_or(0, |sq| KEYS.en_passant_files[sq.file().index()])
+}
+
+pub fn side_to_move(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => KEYS.side_to_move,
+    }
+}
+
+/// Key for holding `count` pieces in a pocket, zero for an empty one so
+/// standard positions hash the same.
+pub fn pocket(color: Color, piece: Piece, count: u8) -> u64 {
+    match count {
+        0 => 0,
+        n => KEYS.pockets[color as usize][piece as usize][n as usize - 1],
+    }
+}
+
+/// Key for `count` checks given in three-check, zero for none.
+pub fn checks(color: Color, count: u8) -> u64 {
+    match count {
+        0 => 0,
+        n => KEYS.checks[color as usize][n as usize - 1],
+    }
+}
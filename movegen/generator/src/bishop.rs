@@ -2,74 +2,46 @@ use psce_core::{Bitboard, Square};
 
 use crate::magic::{MagicData, generate_magic_data};
 
+const DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, -1), (1, -1), (-1, 1)];
+
 fn generate_masks() -> [Bitboard; 64] {
     let mut masks = [Bitboard::empty(); 64];
 
-    for square in Bitboard::all_squares() {
-        let (rank, file) = Square::to_rf(square);
-
+    for square in Square::all() {
         let mut mask = Bitboard::empty();
 
-        for (r, f) in (rank + 1..7).zip(file + 1..7) {
-            mask.set(Square::from_rf(r, f));
-        }
-
-        for (r, f) in (1..rank).rev().zip((1..file).rev()) {
-            mask.set(Square::from_rf(r, f));
-        }
+        // the last square of each ray is left out, as nothing lies behind it
+        for (ranks, files) in DIRECTIONS {
+            let mut s = square;
 
-        for (r, f) in (rank + 1..7).zip((1..file).rev()) {
-            mask.set(Square::from_rf(r, f));
+            while let Some(next) = s.offset(ranks, files)
+                && next.offset(ranks, files).is_some()
+            {
+                mask.set(next);
+                s = next;
+            }
         }
 
-        for (r, f) in (1..rank).rev().zip(file + 1..7) {
-            mask.set(Square::from_rf(r, f));
-        }
-
-        masks[square as usize] = mask;
+        masks[square.index()] = mask;
     }
 
     masks
 }
 
-fn generate_attacks(square: u8, occupancy: Bitboard) -> Bitboard {
+fn generate_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
     let mut attacks = Bitboard::empty();
 
-    let (rank, file) = Square::to_rf(square);
-
-    for (r, f) in (rank + 1..8).zip(file + 1..8) {
-        let s = Square::from_rf(r, f);
-        attacks.set(s);
-
-        if occupancy.get(s) {
-            break;
-        }
-    }
-
-    for (r, f) in (0..rank).rev().zip((0..file).rev()) {
-        let s = Square::from_rf(r, f);
-        attacks.set(s);
+    for (ranks, files) in DIRECTIONS {
+        let mut s = square;
 
-        if occupancy.get(s) {
-            break;
-        }
-    }
-
-    for (r, f) in (rank + 1..8).zip((0..file).rev()) {
-        let s = Square::from_rf(r, f);
-        attacks.set(s);
-
-        if occupancy.get(s) {
-            break;
-        }
-    }
+        while let Some(next) = s.offset(ranks, files) {
+            attacks.set(next);
 
-    for (r, f) in (0..rank).rev().zip(file + 1..8) {
-        let s = Square::from_rf(r, f);
-        attacks.set(s);
+            if occupancy.get(next) {
+                break;
+            }
 
-        if occupancy.get(s) {
-            break;
+            s = next;
         }
     }
 
@@ -3,8 +3,8 @@ use psce_core::{Bitboard, Square};
 pub fn generate_king_attacks() -> [Bitboard; 64] {
     let mut attacks = [Bitboard::empty(); 64];
 
-    for square in Bitboard::all_squares() {
-        let bb = Square::to_bb(square);
+    for square in Square::all() {
+        let bb = square.to_bb();
 
         let mut mask = Bitboard::empty();
 
@@ -17,7 +17,7 @@ pub fn generate_king_attacks() -> [Bitboard; 64] {
         mask |= bb.west() & Bitboard::NOT_FILE_H;
         mask |= bb.north().west() & Bitboard::NOT_FILE_H;
 
-        attacks[square as usize] = mask;
+        attacks[square.index()] = mask;
     }
 
     attacks
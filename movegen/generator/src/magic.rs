@@ -1,4 +1,4 @@
-use psce_core::Bitboard;
+use psce_core::{Bitboard, Square};
 
 #[derive(Debug)]
 pub struct MagicData {
@@ -32,8 +32,8 @@ fn mask_occupancy(attack_mask: Bitboard, mut index: usize) -> Bitboard {
 fn generate_shifts(masks: &[Bitboard; 64]) -> [u8; 64] {
     let mut shifts = [0; 64];
 
-    for square in Bitboard::all_squares() {
-        shifts[square as usize] = 64 - masks[square as usize].count();
+    for square in Square::all() {
+        shifts[square.index()] = 64 - masks[square.index()].count();
     }
 
     shifts
@@ -41,12 +41,12 @@ fn generate_shifts(masks: &[Bitboard; 64]) -> [u8; 64] {
 
 fn generate_magic_and_attacks<FA>(
     generate_attacks: FA,
-    square: u8,
+    square: Square,
     mask: Bitboard,
     shift: u8,
 ) -> (u64, Vec<Bitboard>)
 where
-    FA: Fn(u8, Bitboard) -> Bitboard,
+    FA: Fn(Square, Bitboard) -> Bitboard,
 {
     let entries = 1 << mask.count();
 
@@ -94,7 +94,7 @@ where
 pub(crate) fn generate_magic_data<FM, FA>(generate_masks: FM, generate_attacks: FA) -> MagicData
 where
     FM: Fn() -> [Bitboard; 64],
-    FA: Fn(u8, Bitboard) -> Bitboard,
+    FA: Fn(Square, Bitboard) -> Bitboard,
 {
     let masks = generate_masks();
     let shifts = generate_shifts(&masks);
@@ -103,16 +103,16 @@ where
     let mut offsets = [0; 64];
     let mut attacks = Vec::new();
 
-    for square in Bitboard::all_squares() {
+    for square in Square::all() {
         let (m, a) = generate_magic_and_attacks(
             &generate_attacks,
             square,
-            masks[square as usize],
-            shifts[square as usize],
+            masks[square.index()],
+            shifts[square.index()],
         );
 
-        magics[square as usize] = m;
-        offsets[square as usize] = attacks.len();
+        magics[square.index()] = m;
+        offsets[square.index()] = attacks.len();
         attacks.extend(a);
     }
 
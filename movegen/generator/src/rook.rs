@@ -2,65 +2,46 @@ use psce_core::{Bitboard, Square};
 
 use crate::magic::{MagicData, generate_magic_data};
 
+const DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
 fn generate_masks() -> [Bitboard; 64] {
     let mut masks = [Bitboard::empty(); 64];
 
-    for square in Bitboard::all_squares() {
-        let (rank, file) = Square::to_rf(square);
-
+    for square in Square::all() {
         let mut mask = Bitboard::empty();
 
-        for i in 1..7 {
-            mask.set(Square::from_rf(i, file));
-            mask.set(Square::from_rf(rank, i));
-        }
+        // the last square of each ray is left out, as nothing lies behind it
+        for (ranks, files) in DIRECTIONS {
+            let mut s = square;
 
-        mask.clear(square);
+            while let Some(next) = s.offset(ranks, files)
+                && next.offset(ranks, files).is_some()
+            {
+                mask.set(next);
+                s = next;
+            }
+        }
 
-        masks[square as usize] = mask;
+        masks[square.index()] = mask;
     }
 
     masks
 }
 
-fn generate_attacks(square: u8, occupancy: Bitboard) -> Bitboard {
+fn generate_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
     let mut attacks = Bitboard::empty();
 
-    let (rank, file) = Square::to_rf(square);
-
-    for r in (rank + 1)..8 {
-        let s = Square::from_rf(r, file);
-        attacks.set(s);
-
-        if occupancy.get(s) {
-            break;
-        }
-    }
+    for (ranks, files) in DIRECTIONS {
+        let mut s = square;
 
-    for r in (0..rank).rev() {
-        let s = Square::from_rf(r, file);
-        attacks.set(s);
-
-        if occupancy.get(s) {
-            break;
-        }
-    }
-
-    for f in (file + 1)..8 {
-        let s = Square::from_rf(rank, f);
-        attacks.set(s);
-
-        if occupancy.get(s) {
-            break;
-        }
-    }
+        while let Some(next) = s.offset(ranks, files) {
+            attacks.set(next);
 
-    for f in (0..file).rev() {
-        let s = Square::from_rf(rank, f);
-        attacks.set(s);
+            if occupancy.get(next) {
+                break;
+            }
 
-        if occupancy.get(s) {
-            break;
+            s = next;
         }
     }
 
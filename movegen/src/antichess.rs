@@ -0,0 +1,13 @@
+use psce_core::Move;
+
+use crate::MoveGen;
+
+impl MoveGen {
+    /// Antichess makes capturing compulsory: when any capture is available,
+    /// only captures may be played.
+    pub(crate) fn force_captures(moves: &mut Vec<Move>) {
+        if moves.iter().any(|mv| mv.flags().is_capture()) {
+            moves.retain(|mv| mv.flags().is_capture());
+        }
+    }
+}
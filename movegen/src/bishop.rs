@@ -1,4 +1,4 @@
-use psce_core::{Bitboard, Move, MoveFlags, Piece, Position};
+use psce_core::{Bitboard, Move, MoveFlags, Piece, Position, Square};
 use psce_movegen_generated::{
     BISHOP_ATTACKS, BISHOP_MAGICS, BISHOP_MASKS, BISHOP_OFFSETS, BISHOP_SHIFTS,
 };
@@ -6,11 +6,11 @@ use psce_movegen_generated::{
 use crate::{MoveGen, magic::magic_index};
 
 impl MoveGen {
-    pub(crate) fn bishop_attacks(square: u8, occupancy: Bitboard) -> Bitboard {
-        let masked = occupancy & Bitboard::new(BISHOP_MASKS[square as usize]);
-        let magic_number = BISHOP_MAGICS[square as usize];
-        let shift = BISHOP_SHIFTS[square as usize];
-        let offset = BISHOP_OFFSETS[square as usize];
+    pub(crate) fn bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+        let masked = occupancy & Bitboard::new(BISHOP_MASKS[square.index()]);
+        let magic_number = BISHOP_MAGICS[square.index()];
+        let shift = BISHOP_SHIFTS[square.index()];
+        let offset = BISHOP_OFFSETS[square.index()];
 
         let index = magic_index(masked, magic_number, shift) + offset;
 
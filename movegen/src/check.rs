@@ -0,0 +1,70 @@
+use psce_core::{Castling, Move, Piece, Position, Square, Variant};
+
+use crate::MoveGen;
+
+impl MoveGen {
+    /// Whether `mv` puts the other king in check, worked out without making
+    /// the move. This covers direct checks, discovered checks, the rook of
+    /// a castling move and pawns uncovering a line by capturing en passant.
+    pub fn gives_check(position: &Position, mv: &Move) -> bool {
+        let color = position.side_to_move();
+
+        match position.variant() {
+            Variant::Antichess => return false,
+            // explosions remove more than the captured piece
+            Variant::Atomic => {
+                let mut after = position.clone();
+                after.make_move(mv);
+
+                return Self::is_in_check(&after, !color);
+            }
+            _ => {}
+        }
+
+        let Some(king) = position.king_square(!color) else {
+            return false;
+        };
+
+        let mut occupied = position.bitboards().all();
+        let mut pieces = Piece::ALL.map(|piece| position.bitboards().piece(color, piece));
+
+        if mv.flags().is_castling() {
+            let side = Castling::of_move(mv);
+
+            // both leave first, as in Chess960 they may land on each
+            // other's square
+            for (piece, square) in [(Piece::King, mv.from()), (Piece::Rook, mv.to())] {
+                occupied.clear(square);
+                pieces[piece as usize].clear(square);
+            }
+
+            for (piece, square) in [
+                (Piece::King, side.king_destination()),
+                (Piece::Rook, side.rook_destination()),
+            ] {
+                occupied.set(square);
+                pieces[piece as usize].set(square);
+            }
+        } else {
+            if !mv.flags().is_drop() {
+                occupied.clear(mv.from());
+                pieces[mv.piece() as usize].clear(mv.from());
+            }
+
+            occupied.set(mv.to());
+            pieces[mv.promotion().unwrap_or(mv.piece()) as usize].set(mv.to());
+        }
+
+        if mv.flags().is_en_passant() {
+            occupied.clear(Square::from_rf(mv.from().rank(), mv.to().file()));
+        }
+
+        let own = |piece: Piece| pieces[piece as usize];
+        let checkers = (Self::pawn_attacks(!color, king) & own(Piece::Pawn))
+            | (Self::knight_attacks(king) & own(Piece::Knight))
+            | (Self::bishop_attacks(king, occupied) & (own(Piece::Bishop) | own(Piece::Queen)))
+            | (Self::rook_attacks(king, occupied) & (own(Piece::Rook) | own(Piece::Queen)));
+
+        checkers.is_not_empty()
+    }
+}
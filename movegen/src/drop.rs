@@ -0,0 +1,34 @@
+use psce_core::{Bitboard, Move, Piece, Position};
+
+use crate::MoveGen;
+
+/// Ranks a pawn may not be dropped on, the first and the eighth.
+const BACK_RANKS: Bitboard = Bitboard::new(0xff00_0000_0000_00ff);
+
+impl MoveGen {
+    pub(crate) fn drop_pseudo_legals(position: &Position, moves: &mut Vec<Move>) {
+        let color = position.side_to_move();
+        let empty = !position.bitboards().all();
+
+        for piece in [
+            Piece::Pawn,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Rook,
+            Piece::Queen,
+        ] {
+            if position.pocket(color, piece) == 0 {
+                continue;
+            }
+
+            let targets = match piece {
+                Piece::Pawn => empty & !BACK_RANKS,
+                _ => empty,
+            };
+
+            for square in targets.squares() {
+                moves.push(Move::new_drop(piece, square));
+            }
+        }
+    }
+}
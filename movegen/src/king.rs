@@ -1,27 +1,28 @@
-use psce_core::{Bitboard, Castling, Color, Move, MoveFlags, Piece, Position, Square};
+use psce_core::{Bitboard, Castling, Move, MoveFlags, Piece, Position, Square, Variant};
 use psce_movegen_generated::KING_ATTACKS;
 
 use crate::MoveGen;
 
 impl MoveGen {
-    pub(crate) fn king_attacks(square: u8) -> Bitboard {
-        Bitboard::new(KING_ATTACKS[square as usize])
+    pub(crate) fn king_attacks(square: Square) -> Bitboard {
+        Bitboard::new(KING_ATTACKS[square.index()])
     }
 
     pub(crate) fn king_pseudo_legals(position: &Position, moves: &mut Vec<Move>) {
         let color = position.side_to_move();
 
-        let (king_side_castle_flag, queen_side_castle_flag) = match color {
-            Color::White => (Castling::WHITE_KING_SIDE, Castling::WHITE_QUEEN_SIDE),
-            Color::Black => (Castling::BLACK_KING_SIDE, Castling::BLACK_QUEEN_SIDE),
-        };
-
         let kings = position.bitboards().piece(color, Piece::King);
         let all = position.bitboards().all();
         let own = position.bitboards().color(color);
 
+        // an atomic king would explode itself by capturing
+        let targets = match position.variant() {
+            Variant::Atomic => !all,
+            _ => !own,
+        };
+
         for from_square in kings.squares() {
-            let attacks = Self::king_attacks(from_square) & !own;
+            let attacks = Self::king_attacks(from_square) & targets;
 
             for to_square in attacks.squares() {
                 let capture = all.get(to_square);
@@ -36,64 +37,23 @@ impl MoveGen {
             }
         }
 
-        if position.castling().can(king_side_castle_flag) {
-            let (mask, e_square, f_square, g_square) = match color {
-                Color::White => (
-                    Castling::WHITE_KING_SIDE_SQUARES,
-                    Square::E1,
-                    Square::F1,
-                    Square::G1,
-                ),
-                Color::Black => (
-                    Castling::BLACK_KING_SIDE_SQUARES,
-                    Square::E8,
-                    Square::F8,
-                    Square::G8,
-                ),
-            };
+        let Some(king) = position.king_square(color) else {
+            return;
+        };
 
-            if (all & mask).is_empty()
-                && !Self::is_attacked(position, e_square, !color)
-                && !Self::is_attacked(position, f_square, !color)
-            {
-                moves.push(Move::new(
-                    e_square,
-                    g_square,
-                    Piece::King,
-                    None,
-                    MoveFlags::CASTLING,
-                ));
+        for side in [Castling::side(color, true), Castling::side(color, false)] {
+            if !position.can_castle(side) {
+                continue;
             }
-        }
 
-        if position.castling().can(queen_side_castle_flag) {
-            let (mask, e_square, d_square, c_square) = match color {
-                Color::White => (
-                    Castling::WHITE_QUEEN_SIDE_SQUARES,
-                    Square::E1,
-                    Square::D1,
-                    Square::C1,
-                ),
-                Color::Black => (
-                    Castling::BLACK_QUEEN_SIDE_SQUARES,
-                    Square::E8,
-                    Square::D8,
-                    Square::C8,
-                ),
-            };
-
-            if (all & mask).is_empty()
-                && !Self::is_attacked(position, e_square, !color)
-                && !Self::is_attacked(position, d_square, !color)
-            {
-                moves.push(Move::new(
-                    e_square,
-                    c_square,
-                    Piece::King,
-                    None,
-                    MoveFlags::CASTLING,
-                ));
-            }
+            let rook = position.castling_rook(side).unwrap();
+            moves.push(Move::new(
+                king,
+                rook,
+                Piece::King,
+                None,
+                MoveFlags::CASTLING,
+            ));
         }
     }
 }
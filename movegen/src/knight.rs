@@ -1,11 +1,11 @@
-use psce_core::{Bitboard, Move, MoveFlags, Piece, Position};
+use psce_core::{Bitboard, Move, MoveFlags, Piece, Position, Square};
 use psce_movegen_generated::KNIGHT_ATTACKS;
 
 use crate::MoveGen;
 
 impl MoveGen {
-    pub(crate) fn knight_attacks(square: u8) -> Bitboard {
-        Bitboard::new(KNIGHT_ATTACKS[square as usize])
+    pub(crate) fn knight_attacks(square: Square) -> Bitboard {
+        Bitboard::new(KNIGHT_ATTACKS[square.index()])
     }
 
     pub(crate) fn knight_pseudo_legals(position: &Position, moves: &mut Vec<Move>) {
@@ -1,6 +1,9 @@
-use psce_core::{Color, Move, Piece, Position};
+use psce_core::{Color, Move, Piece, Position, Square, Variant};
 
+mod antichess;
 mod bishop;
+mod check;
+mod drop;
 mod king;
 mod knight;
 mod magic;
@@ -14,6 +17,10 @@ impl MoveGen {
     pub fn pseudo_legals(position: &Position) -> Vec<Move> {
         let mut moves = Vec::new();
 
+        if position.variant_winner().is_some() {
+            return moves;
+        }
+
         Self::pawn_pseudo_legals(position, &mut moves);
         Self::knight_pseudo_legals(position, &mut moves);
         Self::bishop_pseudo_legals(position, &mut moves);
@@ -21,6 +28,12 @@ impl MoveGen {
         Self::queen_pseudo_legals(position, &mut moves);
         Self::king_pseudo_legals(position, &mut moves);
 
+        match position.variant() {
+            Variant::Crazyhouse => Self::drop_pseudo_legals(position, &mut moves),
+            Variant::Antichess => Self::force_captures(&mut moves),
+            _ => {}
+        }
+
         moves
     }
 
@@ -33,15 +46,48 @@ impl MoveGen {
 
                 p.make_move(m);
 
-                let king_square = p.king_square(own_color).expect("should have a king");
-                let is_king_attacked = Self::is_attacked(&p, king_square, p.side_to_move());
-
-                !is_king_attacked
+                Self::leaves_king_safe(&p, own_color)
             })
             .collect()
     }
 
-    pub fn is_attacked(position: &Position, square: u8, by_side: Color) -> bool {
+    /// Whether the king of `color` could be captured if it were the other
+    /// side's turn.
+    pub fn is_in_check(position: &Position, color: Color) -> bool {
+        if position.variant() == Variant::Antichess {
+            return false;
+        }
+
+        let Some(king_square) = position.king_square(color) else {
+            return false;
+        };
+
+        // touching kings cannot take each other without exploding
+        if position.variant() == Variant::Atomic
+            && position
+                .king_square(!color)
+                .is_some_and(|other| Self::king_attacks(king_square).get(other))
+        {
+            return false;
+        }
+
+        Self::is_attacked(position, king_square, !color)
+    }
+
+    /// Whether `color`, which just moved to reach `position`, did so
+    /// without leaving its king in check.
+    pub fn leaves_king_safe(position: &Position, color: Color) -> bool {
+        match position.variant() {
+            Variant::Atomic => {
+                position.king_square(color).is_some()
+                    && (position.king_square(!color).is_none()
+                        || !Self::is_in_check(position, color))
+            }
+            _ => !Self::is_in_check(position, color),
+        }
+    }
+
+    pub fn is_attacked(position: &Position, square: Square, by_side: Color) -> bool {
         if (Self::pawn_attacks(!by_side, square) & position.bitboards().piece(by_side, Piece::Pawn))
             .is_not_empty()
         {
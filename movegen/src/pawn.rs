@@ -1,11 +1,11 @@
-use psce_core::{Bitboard, Color, Move, MoveFlags, Piece, Position, Square};
+use psce_core::{Bitboard, Color, Move, MoveFlags, Piece, Position, Square, Variant};
 use psce_movegen_generated::PAWN_ATTACKS;
 
 use crate::MoveGen;
 
 impl MoveGen {
-    pub(crate) fn pawn_attacks(color: Color, square: u8) -> Bitboard {
-        Bitboard::new(PAWN_ATTACKS[square as usize + color as usize * 64])
+    pub(crate) fn pawn_attacks(color: Color, square: Square) -> Bitboard {
+        Bitboard::new(PAWN_ATTACKS[square.index() + color as usize * 64])
     }
 
     pub(crate) fn pawn_pseudo_legals(position: &Position, moves: &mut Vec<Move>) {
@@ -16,7 +16,29 @@ impl MoveGen {
             Color::Black => (Bitboard::RANK_1, Bitboard::RANK_5),
         };
 
-        let promotion_pieces = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+        // antichess kings are ordinary pieces a pawn may promote to
+        let promotion_pieces: &[Piece] = match position.variant() {
+            Variant::Antichess => &[
+                Piece::Queen,
+                Piece::Rook,
+                Piece::Bishop,
+                Piece::Knight,
+                Piece::King,
+            ],
+            _ => &[Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight],
+        };
+
+        // moves are found by shifting bitboards, so stepping back from
+        // their targets stays on the board
+        let back = match color {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        let behind = |square: Square, ranks: i8, files: i8| {
+            square
+                .offset(ranks * back, files)
+                .expect("pawn moves start on the board")
+        };
 
         let pawns = position.bitboards().piece(color, Piece::Pawn);
         let all = position.bitboards().all();
@@ -30,13 +52,10 @@ impl MoveGen {
             };
 
         for to_square in single_pushes.squares() {
-            let from_square = match color {
-                Color::White => Square::south(to_square),
-                Color::Black => Square::north(to_square),
-            };
+            let from_square = behind(to_square, 1, 0);
 
             if promotion_rank.get(to_square) {
-                for promotion_piece in promotion_pieces {
+                for &promotion_piece in promotion_pieces {
                     moves.push(Move::new(
                         from_square,
                         to_square,
@@ -64,10 +83,7 @@ impl MoveGen {
             };
 
         for to_square in double_pushes.squares() {
-            let from_square = match color {
-                Color::White => Square::south(Square::south(to_square)),
-                Color::Black => Square::north(Square::north(to_square)),
-            };
+            let from_square = behind(to_square, 2, 0);
 
             moves.push(Move::new(
                 from_square,
@@ -86,13 +102,10 @@ impl MoveGen {
         let east_captures = east_attacks & opponent;
 
         for to_square in east_captures.squares() {
-            let from_square = match color {
-                Color::White => Square::west(Square::south(to_square)),
-                Color::Black => Square::west(Square::north(to_square)),
-            };
+            let from_square = behind(to_square, 1, -1);
 
             if promotion_rank.get(to_square) {
-                for promotion_piece in promotion_pieces {
+                for &promotion_piece in promotion_pieces {
                     moves.push(Move::new(
                         from_square,
                         to_square,
@@ -120,13 +133,10 @@ impl MoveGen {
         let west_captures = west_attacks & opponent;
 
         for to_square in west_captures.squares() {
-            let from_square = match color {
-                Color::White => Square::east(Square::south(to_square)),
-                Color::Black => Square::east(Square::north(to_square)),
-            };
+            let from_square = behind(to_square, 1, 1);
 
             if promotion_rank.get(to_square) {
-                for promotion_piece in promotion_pieces {
+                for &promotion_piece in promotion_pieces {
                     moves.push(Move::new(
                         from_square,
                         to_square,
@@ -148,10 +158,7 @@ impl MoveGen {
 
         if let Some(en_passant_square) = position.en_passant_square() {
             if east_attacks.get(en_passant_square) {
-                let from_square = match color {
-                    Color::White => Square::west(Square::south(en_passant_square)),
-                    Color::Black => Square::west(Square::north(en_passant_square)),
-                };
+                let from_square = behind(en_passant_square, 1, -1);
 
                 moves.push(Move::new(
                     from_square,
@@ -163,10 +170,7 @@ impl MoveGen {
             }
 
             if west_attacks.get(en_passant_square) {
-                let from_square = match color {
-                    Color::White => Square::east(Square::south(en_passant_square)),
-                    Color::Black => Square::east(Square::north(en_passant_square)),
-                };
+                let from_square = behind(en_passant_square, 1, 1);
 
                 moves.push(Move::new(
                     from_square,
@@ -1,9 +1,9 @@
-use psce_core::{Bitboard, Move, MoveFlags, Piece, Position};
+use psce_core::{Bitboard, Move, MoveFlags, Piece, Position, Square};
 
 use crate::MoveGen;
 
 impl MoveGen {
-    pub(crate) fn queen_attacks(square: u8, occupancy: Bitboard) -> Bitboard {
+    pub(crate) fn queen_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
         Self::bishop_attacks(square, occupancy) | Self::rook_attacks(square, occupancy)
     }
 
@@ -1,14 +1,14 @@
-use psce_core::{Bitboard, Move, MoveFlags, Piece, Position};
+use psce_core::{Bitboard, Move, MoveFlags, Piece, Position, Square};
 use psce_movegen_generated::{ROOK_ATTACKS, ROOK_MAGICS, ROOK_MASKS, ROOK_OFFSETS, ROOK_SHIFTS};
 
 use crate::{MoveGen, magic::magic_index};
 
 impl MoveGen {
-    pub(crate) fn rook_attacks(square: u8, occupancy: Bitboard) -> Bitboard {
-        let masked = occupancy & Bitboard::new(ROOK_MASKS[square as usize]);
-        let magic_number = ROOK_MAGICS[square as usize];
-        let shift = ROOK_SHIFTS[square as usize];
-        let offset = ROOK_OFFSETS[square as usize];
+    pub(crate) fn rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+        let masked = occupancy & Bitboard::new(ROOK_MASKS[square.index()]);
+        let magic_number = ROOK_MAGICS[square.index()];
+        let shift = ROOK_SHIFTS[square.index()];
+        let offset = ROOK_OFFSETS[square.index()];
 
         let index = magic_index(masked, magic_number, shift) + offset;
 
@@ -0,0 +1,318 @@
+use std::{fs, path::Path};
+
+use psce_core::{Castling, Color, Move, Piece, Position, Square};
+use psce_movegen::MoveGen;
+
+const KEYS_LEN: usize = 781;
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+const ENTRY_SIZE: usize = 16;
+
+/// The `Random64` table used to hash positions in Polyglot books: 768 piece
+/// keys, 4 castling keys, 8 en passant file keys and the side to move key.
+///
+/// The table is not embedded in the engine and has to be loaded from a file
+/// holding the 781 keys as big-endian `u64`s in the Polyglot order.
+#[derive(Debug)]
+pub struct PolyglotKeys {
+    keys: Box<[u64]>,
+}
+
+impl PolyglotKeys {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read book keys: {}", e))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != KEYS_LEN * 8 {
+            return Err("Invalid book keys size".to_string());
+        }
+
+        Ok(Self {
+            keys: bytes
+                .chunks_exact(8)
+                .map(|c| u64::from_be_bytes(c.try_into().unwrap()))
+                .collect(),
+        })
+    }
+
+    pub fn key(&self, pos: &Position) -> u64 {
+        let mut key = 0;
+
+        for square in Square::all() {
+            if let Some((color, piece)) = pos.piece_at(square) {
+                // polyglot orders black before white for every piece type
+                let kind = piece as usize * 2 + (color == Color::White) as usize;
+
+                key ^= self.keys[kind * 64 + square.index()];
+            }
+        }
+
+        for (index, castling) in [
+            Castling::WHITE_KING_SIDE,
+            Castling::WHITE_QUEEN_SIDE,
+            Castling::BLACK_KING_SIDE,
+            Castling::BLACK_QUEEN_SIDE,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if pos.castling().can(castling) {
+                key ^= self.keys[CASTLING_OFFSET + index];
+            }
+        }
+
+        // the en passant file only counts if a pawn can actually capture
+        if let Some(square) = pos
+            .en_passant_square()
+            .filter(|&sq| can_capture_en_passant(pos, sq))
+        {
+            key ^= self.keys[EN_PASSANT_OFFSET + square.file().index()];
+        }
+
+        if pos.side_to_move() == Color::White {
+            key ^= self.keys[TURN_OFFSET];
+        }
+
+        key
+    }
+}
+
+fn can_capture_en_passant(pos: &Position, square: Square) -> bool {
+    let color = pos.side_to_move();
+    let back = if color == Color::White { -1 } else { 1 };
+
+    [-1, 1]
+        .into_iter()
+        .filter_map(|files| square.offset(back, files))
+        .any(|sq| pos.piece_at(sq) == Some((color, Piece::Pawn)))
+}
+
+/// Encodes `mv` the way Polyglot books store it, with castling written as
+/// the king capturing its own rook.
+pub fn encode_move(mv: &Move) -> u16 {
+    let (from, to) = (mv.from(), mv.to());
+
+    let promotion = match mv.promotion() {
+        None => 0,
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(_) => 4,
+    };
+
+    to.file().index() as u16
+        | (to.rank().index() as u16) << 3
+        | (from.file().index() as u16) << 6
+        | (from.rank().index() as u16) << 9
+        | promotion << 12
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BookSelection {
+    /// Always plays the move with the highest weight.
+    Best,
+    /// Picks a move at random proportionally to its weight.
+    Weighted,
+}
+
+/// A Polyglot opening book, with entries sorted by key.
+#[derive(Debug)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read book: {}", e))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err("Invalid book size".to_string());
+        }
+
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|c| BookEntry {
+                key: u64::from_be_bytes(c[0..8].try_into().unwrap()),
+                mv: u16::from_be_bytes(c[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(c[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(c[12..16].try_into().unwrap()),
+            })
+            .collect();
+
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by_key(|e| (e.key, u16::MAX - e.weight));
+
+        Self { entries }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+
+        for entry in &self.entries {
+            bytes.extend(entry.key.to_be_bytes());
+            bytes.extend(entry.mv.to_be_bytes());
+            bytes.extend(entry.weight.to_be_bytes());
+            bytes.extend(entry.learn.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the legal book moves for `pos` with their weights.
+    pub fn moves(&self, keys: &PolyglotKeys, pos: &Position) -> Vec<(Move, u16)> {
+        let key = keys.key(pos);
+        let start = self.entries.partition_point(|e| e.key < key);
+        let legal_moves = MoveGen::legals(pos);
+
+        self.entries[start..]
+            .iter()
+            .take_while(|e| e.key == key)
+            .filter_map(|e| {
+                legal_moves
+                    .iter()
+                    .find(|mv| encode_move(mv) == e.mv)
+                    .map(|mv| (*mv, e.weight))
+            })
+            .collect()
+    }
+
+    pub fn pick(
+        &self,
+        keys: &PolyglotKeys,
+        pos: &Position,
+        selection: BookSelection,
+    ) -> Option<Move> {
+        let moves = self.moves(keys, pos);
+
+        match selection {
+            BookSelection::Best => moves.iter().max_by_key(|(_, weight)| *weight),
+            BookSelection::Weighted => {
+                let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+
+                if total == 0 {
+                    moves.first()
+                } else {
+                    let mut target = fastrand::u32(0..total);
+
+                    moves.iter().find(|(_, weight)| {
+                        let found = target < *weight as u32;
+                        target = target.saturating_sub(*weight as u32);
+                        found
+                    })
+                }
+            }
+        }
+        .map(|(mv, _)| *mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_keys() -> PolyglotKeys {
+        let mut rng = fastrand::Rng::with_seed(7);
+        let bytes: Vec<u8> = (0..KEYS_LEN)
+            .flat_map(|_| rng.u64(..).to_be_bytes())
+            .collect();
+
+        PolyglotKeys::from_bytes(&bytes).unwrap()
+    }
+
+    fn find_move(pos: &Position, uci: &str) -> Move {
+        *MoveGen::legals(pos)
+            .iter()
+            .find(|mv| mv.to_string() == uci)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_encode_castling() {
+        let pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        // e1h1 and e1a1
+        assert_eq!(encode_move(&find_move(&pos, "e1g1")), 4 << 6 | 7);
+        assert_eq!(encode_move(&find_move(&pos, "e1c1")), 4 << 6);
+    }
+
+    #[test]
+    fn test_en_passant_key() {
+        let keys = test_keys();
+
+        let capturable = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let capturable_without = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(keys.key(&capturable), keys.key(&capturable_without));
+
+        let not_capturable = Position::from_fen("4k3/8/8/3p3P/8/8/8/4K3 w - d6 0 1").unwrap();
+        let not_capturable_without =
+            Position::from_fen("4k3/8/8/3p3P/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(keys.key(&not_capturable), keys.key(&not_capturable_without));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let keys = test_keys();
+        let pos = Position::initial();
+        let key = keys.key(&pos);
+
+        let book = Book::from_entries(vec![
+            BookEntry {
+                key,
+                mv: encode_move(&find_move(&pos, "d2d4")),
+                weight: 10,
+                learn: 0,
+            },
+            BookEntry {
+                key,
+                mv: encode_move(&find_move(&pos, "e2e4")),
+                weight: 20,
+                learn: 0,
+            },
+            BookEntry {
+                key: key ^ 1,
+                mv: encode_move(&find_move(&pos, "a2a3")),
+                weight: 30,
+                learn: 0,
+            },
+        ]);
+
+        let book = Book::from_bytes(&book.to_bytes()).unwrap();
+
+        assert_eq!(book.len(), 3);
+        assert_eq!(book.moves(&keys, &pos).len(), 2);
+        assert_eq!(
+            book.pick(&keys, &pos, BookSelection::Best),
+            Some(find_move(&pos, "e2e4"))
+        );
+
+        let weighted = book.pick(&keys, &pos, BookSelection::Weighted).unwrap();
+        assert!(["d2d4", "e2e4"].contains(&weighted.to_string().as_str()));
+    }
+}
@@ -1,19 +1,142 @@
+use std::fmt::Display;
+
 use psce_core::{Color, Piece, Position, Square};
 
 pub const CHECKMATE_SCORE: i32 = 1_000_000;
 
+const MATE_THRESHOLD: i32 = CHECKMATE_SCORE - 1_000;
+
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_THRESHOLD
+}
+
+/// Converts a mate score to the number of moves until mate, negative when
+/// the side to move is getting mated.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if !is_mate_score(score) {
+        return None;
+    }
+
+    let plies = CHECKMATE_SCORE - score.abs();
+    let moves = (plies + 1) / 2;
+
+    Some(if score > 0 { moves } else { -moves })
+}
+
+/// Formats a score as centipawns or as `mate N` for display.
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    pub material: [i32; 6],
+    pub psts: [[i32; 64]; 6],
+}
+
+impl EvalParams {
+    pub const LEN: usize = 6 + 6 * 64;
+
+    pub fn get(&self, index: usize) -> i32 {
+        if index < 6 {
+            self.material[index]
+        } else {
+            self.psts[(index - 6) / 64][(index - 6) % 64]
+        }
+    }
+
+    pub fn set(&mut self, index: usize, value: i32) {
+        if index < 6 {
+            self.material[index] = value;
+        } else {
+            self.psts[(index - 6) / 64][(index - 6) % 64] = value;
+        }
+    }
+
+    /// Sparse coefficients such that the evaluation equals the sum of
+    /// `coefficient * self.get(index)`, used by the tuner.
+    pub fn features(position: &Position) -> Vec<(usize, i32)> {
+        let mut features = Vec::new();
+
+        for color in Color::ALL {
+            let sign = if color == Color::White { 1 } else { -1 };
+
+            for piece in Piece::ALL {
+                let bitboard = position.bitboards().piece(color, piece);
+
+                for square in bitboard.squares() {
+                    let square = if color == Color::White {
+                        square
+                    } else {
+                        Square::mirror(square)
+                    };
+
+                    features.push((piece as usize, sign));
+                    features.push((6 + piece as usize * 64 + square.index(), sign));
+                }
+            }
+        }
+
+        features
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        DEFAULT_EVAL_PARAMS
+    }
+}
+
+impl Display for EvalParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "pub const DEFAULT_EVAL_PARAMS: EvalParams = EvalParams {{"
+        )?;
+        writeln!(
+            f,
+            "    material: [{}],",
+            self.material.map(|v| v.to_string()).join(", ")
+        )?;
+        writeln!(f, "    psts: [")?;
+
+        for pst in &self.psts {
+            writeln!(f, "        [")?;
+
+            for row in pst.chunks(8) {
+                let row: Vec<_> = row.iter().map(|v| v.to_string()).collect();
+                writeln!(f, "            {}, //", row.join(", "))?;
+            }
+
+            writeln!(f, "        ],")?;
+        }
+
+        writeln!(f, "    ],")?;
+        writeln!(f, "}};")
+    }
+}
+
 pub fn evaluate_position(position: &Position) -> i32 {
+    evaluate_position_with(&DEFAULT_EVAL_PARAMS, position)
+}
+
+pub fn evaluate_position_with(params: &EvalParams, position: &Position) -> i32 {
     let mut score = 0;
 
     for color in Color::ALL {
         for piece in Piece::ALL {
             let bitboard = position.bitboards().piece(color, piece);
+            let material = params.material[piece as usize];
+            let pst = &params.psts[piece as usize];
 
             for square in bitboard.squares() {
                 if color == Color::White {
-                    score += material_score(piece) + square_score(piece, square);
+                    score += material + pst[square.index()];
                 } else {
-                    score -= material_score(piece) + square_score(piece, Square::mirror(square));
+                    score -= material + pst[square.mirror().index()];
                 }
             }
         }
@@ -22,20 +145,10 @@ pub fn evaluate_position(position: &Position) -> i32 {
     score
 }
 
-fn material_score(piece: Piece) -> i32 {
-    match piece {
-        Piece::Pawn => 100,
-        Piece::Knight => 320,
-        Piece::Bishop => 330,
-        Piece::Rook => 500,
-        Piece::Queen => 900,
-        Piece::King => 10000,
-    }
-}
-
-fn square_score(piece: Piece, square: u8) -> i32 {
-    match piece {
-        Piece::Pawn => [
+pub const DEFAULT_EVAL_PARAMS: EvalParams = EvalParams {
+    material: [100, 320, 330, 500, 900, 10000],
+    psts: [
+        [
             0, 0, 0, 0, 0, 0, 0, 0, //
             50, 50, 50, 50, 50, 50, 50, 50, //
             10, 10, 20, 30, 30, 20, 10, 10, //
@@ -44,8 +157,8 @@ fn square_score(piece: Piece, square: u8) -> i32 {
             5, -5, -10, 0, 0, -10, -5, 5, //
             5, 10, 10, -20, -20, 10, 10, 5, //
             0, 0, 0, 0, 0, 0, 0, 0, //
-        ][square as usize],
-        Piece::Knight => [
+        ],
+        [
             -50, -40, -30, -30, -30, -30, -40, -50, //
             -40, -20, 0, 0, 0, 0, -20, -40, //
             -30, 0, 10, 15, 15, 10, 0, -30, //
@@ -54,8 +167,8 @@ fn square_score(piece: Piece, square: u8) -> i32 {
             -30, 5, 10, 15, 15, 10, 5, -30, //
             -40, -20, 0, 5, 5, 0, -20, -40, //
             -50, -40, -30, -30, -30, -30, -40, -50, //
-        ][square as usize],
-        Piece::Bishop => [
+        ],
+        [
             -20, -10, -10, -10, -10, -10, -10, -20, //
             -10, 0, 0, 0, 0, 0, 0, -10, //
             -10, 0, 5, 10, 10, 5, 0, -10, //
@@ -64,8 +177,8 @@ fn square_score(piece: Piece, square: u8) -> i32 {
             -10, 10, 10, 10, 10, 10, 10, -10, //
             -10, 5, 0, 0, 0, 0, 5, -10, //
             -20, -10, -10, -10, -10, -10, -10, -20, //
-        ][square as usize],
-        Piece::Rook => [
+        ],
+        [
             0, 0, 0, 0, 0, 0, 0, 0, //
             5, 10, 10, 10, 10, 10, 10, 5, //
             -5, 0, 0, 0, 0, 0, 0, -5, //
@@ -74,8 +187,8 @@ fn square_score(piece: Piece, square: u8) -> i32 {
             -5, 0, 0, 0, 0, 0, 0, -5, //
             -5, 0, 0, 0, 0, 0, 0, -5, //
             0, 0, 0, 5, 5, 0, 0, 0, //
-        ][square as usize],
-        Piece::Queen => [
+        ],
+        [
             -20, -10, -10, -5, -5, -10, -10, -20, //
             -10, 0, 0, 0, 0, 0, 0, -10, //
             -10, 0, 5, 5, 5, 5, 0, -10, //
@@ -84,7 +197,7 @@ fn square_score(piece: Piece, square: u8) -> i32 {
             -10, 5, 5, 5, 5, 5, 0, -10, //
             -10, 0, 5, 0, 0, 0, 0, -10, //
             -20, -10, -10, -5, -5, -10, -10, -20, //
-        ][square as usize],
-        Piece::King => 0,
-    }
-}
+        ],
+        [0; 64],
+    ],
+};
@@ -1,148 +1,604 @@
-use psce_core::{Color, Move, Piece, Position};
-use psce_movegen::MoveGen;
+use std::{
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
+use psce_core::{Move, Position};
+
+mod book;
 mod eval;
+mod nnue;
+mod ordering;
 mod pv;
+mod searcher;
+mod skill;
+mod syzygy;
+mod tablebase;
+mod tt;
+
+use searcher::{Searcher, Shared};
 
-use eval::CHECKMATE_SCORE;
-use pv::PrincipalVariations;
+pub use book::{Book, BookEntry, BookSelection, PolyglotKeys, encode_move};
+pub use eval::{
+    CHECKMATE_SCORE, EvalParams, evaluate_position, evaluate_position_with, format_score,
+    is_mate_score, mate_in,
+};
+pub use nnue::Network;
+pub use skill::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO, Skill};
+pub use syzygy::Syzygy;
+pub use tablebase::{TB_WIN_SCORE, Tablebase, Wdl};
+pub use tt::{Bound, TranspositionTable};
 
-pub use eval::evaluate_position;
+/// Largest supported `SearchOptions::max_ply`, which keeps mate scores
+/// distinguishable from evaluations.
+pub const MAX_PLY: usize = 512;
 
 #[derive(Default, Debug)]
 pub struct SearchStats {
     pub nodes: u64,
     pub beta_cutoffs: u64,
+    /// Beta cutoffs caused by the first move searched, a measure of move
+    /// ordering quality.
+    pub first_move_cutoffs: u64,
+    pub aspiration_researches: u64,
+    pub tb_hits: u64,
+    /// Deepest ply reached, extensions included.
+    pub seldepth: usize,
+}
+
+impl SearchStats {
+    fn add(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.beta_cutoffs += other.beta_cutoffs;
+        self.first_move_cutoffs += other.first_move_cutoffs;
+        self.aspiration_researches += other.aspiration_researches;
+        self.tb_hits += other.tb_hits;
+        self.seldepth = self.seldepth.max(other.seldepth);
+    }
+}
+
+/// A ranked root move with its score and principal variation.
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub score: i32,
+    pub bound: Bound,
+    pub pv: Vec<Move>,
 }
 
 #[derive(Debug)]
 pub struct SearchResult {
+    pub depth: u8,
     pub score: i32,
     pub pv: Vec<Move>,
+    /// The best `multi_pv` root moves ordered by score; the first line
+    /// matches `score` and `pv`.
+    pub lines: Vec<PvLine>,
     pub stats: SearchStats,
 }
 
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub depth: u8,
+    /// Aborts once this many nodes were searched, keeping the result of the
+    /// last completed iteration.
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    /// Aborts the search when set from another thread.
+    pub stop: Option<Arc<AtomicBool>>,
+    /// Ponders while set: time and node limits are ignored and the search
+    /// does not return until stopped or the flag is cleared, after which
+    /// `movetime` counts from the moment it was cleared.
+    pub ponder: Option<Arc<AtomicBool>>,
+    /// Evaluates with this network instead of `evaluate_position` when set.
+    pub network: Option<Arc<Network>>,
+    pub pruning: Pruning,
+    pub threads: usize,
+    /// Size of the transposition table in megabytes.
+    pub hash_mb: usize,
+    /// Keeps entries between searches when set, instead of allocating a
+    /// table of `hash_mb` for every search.
+    pub tt: Option<Arc<TranspositionTable>>,
+    /// Number of root moves to search with an exact score and PV.
+    pub multi_pv: usize,
+    /// Restricts the root to these moves when not empty.
+    pub search_moves: Vec<Move>,
+    /// Restricts the root to tablebase-optimal moves and cuts off the search
+    /// in covered positions.
+    pub tablebase: Option<Arc<dyn Tablebase>>,
+    /// Limits the depth and nodes and plays sub-optimal moves below full
+    /// strength.
+    pub skill: Skill,
+    /// Plies beyond which extended lines return their static evaluation, at
+    /// most `MAX_PLY`.
+    pub max_ply: usize,
+}
+
+impl SearchOptions {
+    fn is_stopped(&self) -> bool {
+        self.stop
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    fn is_pondering(&self) -> bool {
+        self.ponder
+            .as_ref()
+            .is_some_and(|ponder| ponder.load(Ordering::Relaxed))
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            depth: 0,
+            nodes: None,
+            movetime: None,
+            stop: None,
+            ponder: None,
+            network: None,
+            pruning: Pruning::default(),
+            threads: 1,
+            hash_mb: 16,
+            tt: None,
+            multi_pv: 1,
+            search_moves: Vec::new(),
+            tablebase: None,
+            skill: Skill::default(),
+            max_ply: 128,
+        }
+    }
+}
+
+/// Toggles for the individual pruning and reduction techniques in `negamax`.
+#[derive(Clone, Copy, Debug)]
+pub struct Pruning {
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub principal_variation_search: bool,
+    pub reverse_futility: bool,
+    pub futility: bool,
+}
+
+impl Pruning {
+    pub const ALL: Self = Self {
+        null_move: true,
+        late_move_reductions: true,
+        principal_variation_search: true,
+        reverse_futility: true,
+        futility: true,
+    };
+
+    pub const NONE: Self = Self {
+        null_move: false,
+        late_move_reductions: false,
+        principal_variation_search: false,
+        reverse_futility: false,
+        futility: false,
+    };
+}
+
+impl Default for Pruning {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
 pub fn find_best_move(pos: &Position, depth: u8) -> Option<SearchResult> {
-    let mut pos = pos.clone();
-    let mut pvs = PrincipalVariations::new();
-    let mut stats = SearchStats::default();
+    search(
+        pos,
+        &SearchOptions {
+            depth,
+            ..Default::default()
+        },
+    )
+}
 
-    let score = negamax(
-        &mut pos,
-        depth,
-        0,
-        -CHECKMATE_SCORE,
-        CHECKMATE_SCORE,
-        &mut pvs,
-        &mut stats,
-    );
+/// Runs a Lazy SMP search: every thread searches the same position with its
+/// own move ordering tables, sharing the transposition table. The deepest
+/// completed result wins, preferring the main thread on ties. Only the main
+/// thread searches more than one PV line.
+pub fn search(pos: &Position, options: &SearchOptions) -> Option<SearchResult> {
+    let tablebase_moves = options
+        .tablebase
+        .as_ref()
+        .and_then(|tablebase| tablebase::probe_root(tablebase.as_ref(), pos))
+        .map(|moves| {
+            let allowed: Vec<_> = moves
+                .iter()
+                .filter(|mv| options.search_moves.is_empty() || options.search_moves.contains(mv))
+                .copied()
+                .collect();
 
-    let pv = pvs.get_pv(0);
+            if allowed.is_empty() { moves } else { allowed }
+        });
 
-    if !pv.is_empty() {
-        Some(SearchResult { score, pv, stats })
+    let restricted;
+    let options = match tablebase_moves {
+        Some(search_moves) => {
+            restricted = SearchOptions {
+                search_moves,
+                ..options.clone()
+            };
+            &restricted
+        }
+        None => options,
+    };
+
+    let multi_pv = options.multi_pv;
+    let skill = options.skill;
+
+    let limited;
+    let options = if skill.is_limited() {
+        limited = SearchOptions {
+            depth: options.depth.min(skill.depth()),
+            nodes: Some(
+                options
+                    .nodes
+                    .map_or(skill.nodes(), |n| n.min(skill.nodes())),
+            ),
+            multi_pv: multi_pv.max(skill::CANDIDATES),
+            ..options.clone()
+        };
+        &limited
     } else {
-        None
+        options
+    };
+
+    let shared = Shared {
+        tt: options
+            .tt
+            .clone()
+            .unwrap_or_else(|| Arc::new(TranspositionTable::new(options.hash_mb))),
+        stop: AtomicBool::new(false),
+        nodes: AtomicU64::new(0),
+        start: Instant::now(),
+        ponderhit: OnceLock::new(),
+    };
+
+    let run = |thread_id: usize| {
+        let mut pos = pos.clone();
+        let mut searcher = Searcher::new(&pos, options, &shared);
+        let result = searcher.iterate(&mut pos, thread_id);
+
+        (result, searcher.stats)
+    };
+
+    let results: Vec<_> = thread::scope(|s| {
+        let helpers: Vec<_> = (1..options.threads.max(1))
+            .map(|thread_id| s.spawn(move || run(thread_id)))
+            .collect();
+
+        let main = run(0);
+
+        // a finished search waits for ponderhit or stop before returning
+        while options.is_pondering() && !options.is_stopped() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        shared.stop.store(true, Ordering::Relaxed);
+
+        std::iter::once(main)
+            .chain(helpers.into_iter().map(|h| h.join().unwrap()))
+            .collect()
+    });
+
+    let mut stats = SearchStats::default();
+    for (_, thread_stats) in &results {
+        stats.add(thread_stats);
+    }
+
+    let mut best = results
+        .into_iter()
+        .filter_map(|(result, _)| result)
+        .reduce(|best, result| {
+            if (result.lines.len(), result.depth) > (best.lines.len(), best.depth) {
+                result
+            } else {
+                best
+            }
+        })?;
+
+    if skill.is_limited() {
+        let index = skill.pick(&best.lines, &mut fastrand::Rng::new());
+        let line = best.lines.remove(index);
+
+        best.lines.insert(0, line);
+        best.lines.truncate(multi_pv.max(1));
     }
+
+    Some(SearchResult {
+        depth: best.depth,
+        score: best.lines[0].score,
+        pv: best.lines[0].pv.clone(),
+        lines: best.lines,
+        stats,
+    })
 }
 
-fn negamax(
-    pos: &mut Position,
-    depth: u8,
-    ply: u8,
-    mut alpha: i32,
-    beta: i32,
-    pvs: &mut PrincipalVariations,
-    stats: &mut SearchStats,
-) -> i32 {
-    stats.nodes += 1;
-
-    pvs.clear_ply(ply as usize);
-
-    if depth == 0 {
-        return if pos.side_to_move() == Color::White {
-            evaluate_position(pos)
-        } else {
-            -evaluate_position(pos)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psce_core::Variant;
+
+    #[test]
+    fn test_finds_mate_in_two() {
+        let pos = Position::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let result = find_best_move(&pos, 5).unwrap();
+
+        assert_eq!(result.pv.len(), 3);
+        assert_eq!(mate_in(result.score), Some(2));
+    }
+
+    #[test]
+    fn test_finds_variant_wins() {
+        let mut pos = Position::from_fen("8/8/8/8/8/2K5/8/k7 w - - 0 1").unwrap();
+        pos.set_variant(Variant::KingOfTheHill);
+        let result = find_best_move(&pos, 3).unwrap();
+
+        assert_eq!(result.pv[0].to_string(), "c3d4");
+        assert_eq!(mate_in(result.score), Some(1));
+
+        let pos = Position::from_fen("7k/8/8/8/8/8/8/R6K w - - 1+3 0 1").unwrap();
+        let result = find_best_move(&pos, 3).unwrap();
+
+        assert_eq!(result.pv[0].to_string(), "a1a8");
+        assert_eq!(mate_in(result.score), Some(1));
+
+        // giving the rook away to a forced capture wins antichess
+        let mut pos = Position::from_fen("8/8/8/8/8/8/1k6/R7 w - - 0 1").unwrap();
+        pos.set_variant(Variant::Antichess);
+        let result = find_best_move(&pos, 3).unwrap();
+
+        assert_eq!(mate_in(result.score), Some(1));
+    }
+
+    #[test]
+    fn test_check_extensions_find_mate() {
+        // Qd8+ Bxd8 Re8# lies beyond a three ply search without extensions
+        let pos = Position::from_fen("r1b2k1r/ppp1bppp/8/1B1Q4/5q2/2P5/PPP2PPP/R3R1K1 w - - 1 1")
+            .unwrap();
+        let result = find_best_move(&pos, 3).unwrap();
+
+        assert_eq!(mate_in(result.score), Some(2));
+    }
+
+    #[test]
+    fn test_max_ply_caps_the_search() {
+        let pos = Position::initial();
+        let options = SearchOptions {
+            depth: 6,
+            max_ply: 4,
+            ..Default::default()
         };
+
+        let result = search(&pos, &options).unwrap();
+
+        assert_eq!(result.stats.seldepth, 4);
+        assert!(result.pv.len() <= 4);
     }
 
-    let color = pos.side_to_move();
-    let opponent = !color;
+    /// Nodes of a depth 6 search of the initial position with `pruning`.
+    fn nodes_with(pruning: Pruning) -> u64 {
+        let options = SearchOptions {
+            depth: 6,
+            pruning,
+            ..Default::default()
+        };
 
-    let mut best = -CHECKMATE_SCORE;
-    let mut found_legal = false;
+        search(&Position::initial(), &options).unwrap().stats.nodes
+    }
 
-    let moves = sort_moves(MoveGen::pseudo_legals(pos), pos);
+    #[test]
+    fn test_null_move_pruning_saves_nodes() {
+        let off = Pruning {
+            null_move: false,
+            ..Pruning::ALL
+        };
 
-    for mv in moves {
-        let undo = pos.make_move(&mv);
+        assert!(nodes_with(off) > nodes_with(Pruning::ALL));
+    }
 
-        let king_square = pos.king_square(color).unwrap();
-        let is_king_attacked = MoveGen::is_attacked(pos, king_square, opponent);
+    #[test]
+    fn test_late_move_reductions_save_nodes() {
+        let off = Pruning {
+            late_move_reductions: false,
+            ..Pruning::ALL
+        };
 
-        if is_king_attacked {
-            pos.undo_move(&mv, &undo);
-            continue;
-        }
+        assert!(nodes_with(off) > nodes_with(Pruning::ALL));
+    }
 
-        found_legal = true;
+    #[test]
+    fn test_principal_variation_search_saves_nodes() {
+        let off = Pruning {
+            principal_variation_search: false,
+            ..Pruning::ALL
+        };
 
-        let score = -negamax(pos, depth - 1, ply + 1, -beta, -alpha, pvs, stats);
-        pos.undo_move(&mv, &undo);
+        assert!(nodes_with(off) > nodes_with(Pruning::ALL));
+    }
 
-        if score > best {
-            best = score;
+    #[test]
+    fn test_reverse_futility_pruning_saves_nodes() {
+        let off = Pruning {
+            reverse_futility: false,
+            ..Pruning::ALL
+        };
 
-            pvs.update(ply as usize, mv);
+        assert!(nodes_with(off) > nodes_with(Pruning::ALL));
+    }
 
-            if score > alpha {
-                alpha = score;
-            }
-        }
+    #[test]
+    fn test_futility_pruning_saves_nodes() {
+        let off = Pruning {
+            futility: false,
+            ..Pruning::ALL
+        };
 
-        if alpha >= beta {
-            stats.beta_cutoffs += 1;
-            break;
-        }
+        assert!(nodes_with(off) > nodes_with(Pruning::ALL));
     }
 
-    if !found_legal {
-        let king_square = pos.king_square(color).unwrap();
-        let is_king_attacked = MoveGen::is_attacked(pos, king_square, opponent);
+    #[test]
+    fn test_mate_in() {
+        assert_eq!(mate_in(CHECKMATE_SCORE - 1), Some(1));
+        assert_eq!(mate_in(CHECKMATE_SCORE - 3), Some(2));
+        assert_eq!(mate_in(-CHECKMATE_SCORE + 2), Some(-1));
+        assert_eq!(mate_in(150), None);
+    }
 
-        if is_king_attacked {
-            return -CHECKMATE_SCORE + ply as i32;
-        } else {
-            return 0;
-        }
+    #[test]
+    fn test_single_threaded_is_deterministic() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        let first = find_best_move(&pos, 5).unwrap();
+        let second = find_best_move(&pos, 5).unwrap();
+
+        assert_eq!(first.pv, second.pv);
+        assert_eq!(first.stats.nodes, second.stats.nodes);
     }
 
-    best
-}
+    #[test]
+    fn test_shared_transposition_table() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let tt = Arc::new(TranspositionTable::new(1));
+        let options = SearchOptions {
+            depth: 5,
+            tt: Some(tt.clone()),
+            ..Default::default()
+        };
 
-fn sort_moves(mut moves: Vec<Move>, pos: &Position) -> Vec<Move> {
-    moves.sort_by_cached_key(|mv| {
-        if mv.flags().is_capture() {
-            let victim_value = piece_value(pos.victim_piece(mv).unwrap());
-            let attacker_value = piece_value(mv.piece());
+        let first = search(&pos, &options).unwrap();
+        let second = search(&pos, &options).unwrap();
+        assert!(second.stats.nodes < first.stats.nodes);
 
-            (-victim_value, attacker_value)
-        } else {
-            (0, 0)
-        }
-    });
+        // a cleared table searches like a fresh one
+        tt.clear();
+        let cleared = search(&pos, &options).unwrap();
+        assert_eq!(cleared.stats.nodes, first.stats.nodes);
+    }
 
-    moves
-}
+    #[test]
+    fn test_multithreaded_search() {
+        let pos = Position::initial();
+        let options = SearchOptions {
+            depth: 5,
+            threads: 3,
+            ..Default::default()
+        };
+
+        let result = search(&pos, &options).unwrap();
+
+        assert_eq!(result.depth, 5);
+        assert!(
+            psce_movegen::MoveGen::legals(&pos)
+                .iter()
+                .any(|mv| *mv == result.pv[0])
+        );
+    }
+
+    #[test]
+    fn test_multi_pv() {
+        let pos = Position::initial();
+        let options = SearchOptions {
+            depth: 4,
+            multi_pv: 3,
+            ..Default::default()
+        };
+
+        let result = search(&pos, &options).unwrap();
+
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[0].pv, result.pv);
+        assert!(result.lines.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_ne!(result.lines[0].pv[0], result.lines[1].pv[0]);
+        assert_ne!(result.lines[1].pv[0], result.lines[2].pv[0]);
+        assert_ne!(result.lines[0].pv[0], result.lines[2].pv[0]);
+    }
+
+    #[test]
+    fn test_search_moves() {
+        let pos = Position::initial();
+        let search_moves: Vec<_> = psce_movegen::MoveGen::legals(&pos)
+            .into_iter()
+            .filter(|mv| ["a2a3", "h2h4"].contains(&mv.to_string().as_str()))
+            .collect();
+
+        let options = SearchOptions {
+            depth: 4,
+            multi_pv: 5,
+            search_moves: search_moves.clone(),
+            ..Default::default()
+        };
+
+        let result = search(&pos, &options).unwrap();
+
+        assert_eq!(result.lines.len(), 2);
+        assert!(
+            result
+                .lines
+                .iter()
+                .all(|line| search_moves.contains(&line.pv[0]))
+        );
+    }
+
+    #[test]
+    fn test_skill_limits_the_search() {
+        let options = SearchOptions {
+            depth: 8,
+            skill: Skill::new(4),
+            ..Default::default()
+        };
+
+        let result = search(&Position::initial(), &options).unwrap();
+
+        assert_eq!(result.depth, Skill::new(4).depth());
+        assert_eq!(result.lines.len(), 1);
+        assert!(result.stats.nodes <= Skill::new(4).nodes() + 1);
+    }
+
+    #[test]
+    fn test_ponder() {
+        let movetime = Duration::from_millis(10);
+        let ponder = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let options = SearchOptions {
+            depth: 64,
+            movetime: Some(movetime),
+            ponder: Some(ponder.clone()),
+            stop: Some(stop.clone()),
+            ..Default::default()
+        };
+
+        // stop ends a search that is still pondering
+        let pondering = {
+            let options = options.clone();
+            thread::spawn(move || search(&Position::initial(), &options))
+        };
+
+        assert!(!pondering.is_finished());
+        stop.store(true, Ordering::Relaxed);
+        assert!(!pondering.join().unwrap().unwrap().pv.is_empty());
+
+        // the time limit only starts counting on ponderhit
+        stop.store(false, Ordering::Relaxed);
+        let pondering = thread::spawn(move || search(&Position::initial(), &options));
+
+        assert!(!pondering.is_finished());
+        let ponderhit = Instant::now();
+        ponder.store(false, Ordering::Relaxed);
 
-fn piece_value(piece: Piece) -> i32 {
-    match piece {
-        Piece::Pawn => 1,
-        Piece::Knight => 2,
-        Piece::Bishop => 3,
-        Piece::Rook => 4,
-        Piece::Queen => 5,
-        Piece::King => 99,
+        let result = pondering.join().unwrap().unwrap();
+        assert!(ponderhit.elapsed() >= movetime);
+        assert!(!result.pv.is_empty());
     }
 }
@@ -0,0 +1,415 @@
+use std::{fs, path::Path};
+
+use psce_core::{Castling, Color, Move, Piece, Position, Square};
+
+const MAGIC: &[u8; 8] = b"PSCENNUE";
+
+pub const INPUTS: usize = 768;
+
+const QA: i32 = 255;
+const QB: i32 = 64;
+const SCALE: i32 = 400;
+
+/// A `768 -> N -> 1` network with one accumulator per perspective.
+///
+/// The file format is little-endian: the `PSCENNUE` magic, the hidden size
+/// as `u32`, then the `i16` feature weights (`768 * N`), feature biases
+/// (`N`), output weights (`2 * N`, side to move first) and output bias.
+#[derive(Debug)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+impl Network {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read network: {}", e))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 12 || &bytes[..8] != MAGIC {
+            return Err("Invalid network header".to_string());
+        }
+
+        let hidden = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+
+        let mut values = bytes[12..]
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes([c[0], c[1]]));
+
+        if values.len() != INPUTS * hidden + 3 * hidden + 1 {
+            return Err("Invalid network size".to_string());
+        }
+
+        Ok(Self {
+            hidden,
+            feature_weights: values.by_ref().take(INPUTS * hidden).collect(),
+            feature_biases: values.by_ref().take(hidden).collect(),
+            output_weights: values.by_ref().take(2 * hidden).collect(),
+            output_bias: values.next().unwrap(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.hidden as u32).to_le_bytes());
+
+        for value in self
+            .feature_weights
+            .iter()
+            .chain(&self.feature_biases)
+            .chain(&self.output_weights)
+            .chain([&self.output_bias])
+        {
+            bytes.extend(value.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    fn feature_row(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+}
+
+fn feature(perspective: Color, color: Color, piece: Piece, square: Square) -> usize {
+    let (side, square) = match perspective {
+        Color::White => (color as usize, square),
+        Color::Black => ((!color) as usize, square.mirror()),
+    };
+
+    side * 384 + piece as usize * 64 + square.index()
+}
+
+#[derive(Clone, Debug)]
+struct Accumulator {
+    white: Vec<i16>,
+    black: Vec<i16>,
+}
+
+impl Accumulator {
+    fn perspective_mut(&mut self, perspective: Color) -> &mut [i16] {
+        match perspective {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+}
+
+/// Accumulators for every ply of the current line, pushed and popped in
+/// step with `Position::make_move` and `Position::undo_move`.
+#[derive(Debug)]
+pub struct AccumulatorStack {
+    stack: Vec<Accumulator>,
+}
+
+impl AccumulatorStack {
+    pub fn new(network: &Network, position: &Position) -> Self {
+        let mut accumulator = Accumulator {
+            white: network.feature_biases.clone(),
+            black: network.feature_biases.clone(),
+        };
+
+        for color in Color::ALL {
+            for piece in Piece::ALL {
+                for square in position.bitboards().piece(color, piece).squares() {
+                    for perspective in Color::ALL {
+                        let row = network.feature_row(feature(perspective, color, piece, square));
+                        simd::add(accumulator.perspective_mut(perspective), row);
+                    }
+                }
+            }
+        }
+
+        Self {
+            stack: vec![accumulator],
+        }
+    }
+
+    /// Pushes the accumulator for the position after `mv` was played by
+    /// `color`, capturing `captured` if any and removing the pieces of an
+    /// atomic explosion.
+    pub fn make_move(
+        &mut self,
+        network: &Network,
+        mv: &Move,
+        color: Color,
+        captured: Option<Piece>,
+        exploded: &[(Color, Piece, Square)],
+    ) {
+        let mut added = Vec::with_capacity(2);
+        let mut removed = Vec::with_capacity(2 + exploded.len());
+
+        if !mv.flags().is_drop() {
+            removed.push((color, mv.piece(), mv.from()));
+        }
+
+        if mv.flags().is_castling() {
+            let side = Castling::of_move(mv);
+
+            added.push((color, Piece::King, side.king_destination()));
+            removed.push((color, Piece::Rook, mv.to()));
+            added.push((color, Piece::Rook, side.rook_destination()));
+        } else {
+            added.push((color, mv.promotion().unwrap_or(mv.piece()), mv.to()));
+        }
+
+        if let Some(captured) = captured {
+            let square = if mv.flags().is_en_passant() {
+                Square::from_rf(mv.from().rank(), mv.to().file())
+            } else {
+                mv.to()
+            };
+
+            removed.push((!color, captured, square));
+        }
+
+        // the capturer is among the exploded pieces
+        removed.extend_from_slice(exploded);
+
+        let mut accumulator = self.stack.last().unwrap().clone();
+
+        for perspective in Color::ALL {
+            let values = accumulator.perspective_mut(perspective);
+
+            for &(c, p, sq) in &added {
+                simd::add(values, network.feature_row(feature(perspective, c, p, sq)));
+            }
+
+            for &(c, p, sq) in &removed {
+                simd::sub(values, network.feature_row(feature(perspective, c, p, sq)));
+            }
+        }
+
+        self.stack.push(accumulator);
+    }
+
+    pub fn undo_move(&mut self) {
+        debug_assert!(self.stack.len() > 1);
+
+        self.stack.pop();
+    }
+
+    /// Evaluates the current accumulator from the point of view of `side_to_move`.
+    pub fn evaluate(&self, network: &Network, side_to_move: Color) -> i32 {
+        let accumulator = self.stack.last().unwrap();
+
+        let (us, them) = match side_to_move {
+            Color::White => (&accumulator.white, &accumulator.black),
+            Color::Black => (&accumulator.black, &accumulator.white),
+        };
+
+        let (us_weights, them_weights) = network.output_weights.split_at(network.hidden);
+
+        let sum = simd::crelu_dot(us, us_weights) + simd::crelu_dot(them, them_weights);
+
+        (sum + network.output_bias as i32) * SCALE / (QA * QB)
+    }
+}
+
+mod simd {
+    use super::QA;
+
+    pub fn add(values: &mut [i16], row: &[i16]) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked.
+            return unsafe { avx2::add(values, row) };
+        }
+
+        for (v, r) in values.iter_mut().zip(row) {
+            *v = v.wrapping_add(*r);
+        }
+    }
+
+    pub fn sub(values: &mut [i16], row: &[i16]) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked.
+            return unsafe { avx2::sub(values, row) };
+        }
+
+        for (v, r) in values.iter_mut().zip(row) {
+            *v = v.wrapping_sub(*r);
+        }
+    }
+
+    pub fn crelu_dot(values: &[i16], weights: &[i16]) -> i32 {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked.
+            return unsafe { avx2::crelu_dot(values, weights) };
+        }
+
+        values
+            .iter()
+            .zip(weights)
+            .map(|(&v, &w)| (v as i32).clamp(0, QA) * w as i32)
+            .sum()
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    mod avx2 {
+        use std::arch::x86_64::*;
+
+        use super::QA;
+
+        const LANES: usize = 16;
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn add(values: &mut [i16], row: &[i16]) {
+            let chunks = values.len() / LANES;
+
+            for i in 0..chunks {
+                let v = values[i * LANES..].as_mut_ptr() as *mut __m256i;
+                let r = row[i * LANES..].as_ptr() as *const __m256i;
+
+                unsafe {
+                    _mm256_storeu_si256(
+                        v,
+                        _mm256_add_epi16(_mm256_loadu_si256(v), _mm256_loadu_si256(r)),
+                    );
+                }
+            }
+
+            for i in chunks * LANES..values.len() {
+                values[i] = values[i].wrapping_add(row[i]);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn sub(values: &mut [i16], row: &[i16]) {
+            let chunks = values.len() / LANES;
+
+            for i in 0..chunks {
+                let v = values[i * LANES..].as_mut_ptr() as *mut __m256i;
+                let r = row[i * LANES..].as_ptr() as *const __m256i;
+
+                unsafe {
+                    _mm256_storeu_si256(
+                        v,
+                        _mm256_sub_epi16(_mm256_loadu_si256(v), _mm256_loadu_si256(r)),
+                    );
+                }
+            }
+
+            for i in chunks * LANES..values.len() {
+                values[i] = values[i].wrapping_sub(row[i]);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn crelu_dot(values: &[i16], weights: &[i16]) -> i32 {
+            let chunks = values.len() / LANES;
+            let zero = _mm256_setzero_si256();
+            let max = _mm256_set1_epi16(QA as i16);
+            let mut sum = _mm256_setzero_si256();
+
+            for i in 0..chunks {
+                unsafe {
+                    let v = _mm256_loadu_si256(values[i * LANES..].as_ptr() as *const __m256i);
+                    let w = _mm256_loadu_si256(weights[i * LANES..].as_ptr() as *const __m256i);
+                    let clamped = _mm256_min_epi16(_mm256_max_epi16(v, zero), max);
+
+                    sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clamped, w));
+                }
+            }
+
+            let mut lanes = [0i32; 8];
+            unsafe { _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum) };
+
+            let tail: i32 = (chunks * LANES..values.len())
+                .map(|i| (values[i] as i32).clamp(0, QA) * weights[i] as i32)
+                .sum();
+
+            lanes.iter().sum::<i32>() + tail
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use psce_core::Variant;
+    use psce_movegen::MoveGen;
+
+    use super::*;
+
+    fn random_network(hidden: usize) -> Network {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((hidden as u32).to_le_bytes());
+
+        for _ in 0..INPUTS * hidden + 3 * hidden + 1 {
+            bytes.extend(rng.i16(-64..64).to_le_bytes());
+        }
+
+        Network::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_matches_refresh() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        for variant in [Variant::Standard, Variant::Atomic] {
+            let mut position = Position::from_fen(fen).unwrap();
+            position.set_variant(variant);
+
+            check_incremental(&position);
+        }
+    }
+
+    fn check_incremental(position: &Position) {
+        let network = random_network(40);
+        let mut position = position.clone();
+        let mut stack = AccumulatorStack::new(&network, &position);
+        let initial = stack.evaluate(&network, position.side_to_move());
+
+        for mv in MoveGen::legals(&position) {
+            let color = position.side_to_move();
+            let undo = position.make_move(&mv);
+            stack.make_move(&network, &mv, color, undo.captured_piece, &undo.exploded);
+
+            let refreshed = AccumulatorStack::new(&network, &position);
+            assert_eq!(
+                stack.evaluate(&network, position.side_to_move()),
+                refreshed.evaluate(&network, position.side_to_move()),
+                "{}",
+                mv
+            );
+
+            position.undo_move(&mv, &undo);
+            stack.undo_move();
+        }
+
+        assert_eq!(stack.evaluate(&network, position.side_to_move()), initial);
+    }
+
+    #[test]
+    fn test_accumulate_wraps() {
+        // 40 values cover both the vector lanes and the scalar tail
+        let mut values = vec![i16::MAX; 40];
+        simd::add(&mut values, &[1; 40]);
+        assert!(values.iter().all(|&v| v == i16::MIN));
+
+        simd::sub(&mut values, &[1; 40]);
+        assert!(values.iter().all(|&v| v == i16::MAX));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let network = random_network(8);
+        let loaded = Network::from_bytes(&network.to_bytes()).unwrap();
+
+        assert_eq!(loaded.feature_weights, network.feature_weights);
+        assert_eq!(loaded.output_bias, network.output_bias);
+    }
+}
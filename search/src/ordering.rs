@@ -0,0 +1,247 @@
+use psce_core::{Color, Move, Piece, Position};
+
+const MAX_HISTORY: i32 = 16_384;
+
+const TT_MOVE_SCORE: i32 = 2_000_000;
+const CAPTURE_SCORE: i32 = 1_000_000;
+const KILLER_SCORES: [i32; 2] = [900_000, 800_000];
+const COUNTER_MOVE_SCORE: i32 = 700_000;
+
+/// Quiet move ordering state maintained by the search: per-ply killer
+/// slots, a piece-to history table and counter-moves indexed by the
+/// piece and destination of the previous move.
+#[derive(Debug)]
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; 2]>,
+    history: [[[i32; 64]; 6]; 2],
+    counter_moves: Box<[[[Option<Move>; 64]; 6]; 2]>,
+    played: Vec<Option<Move>>,
+}
+
+impl MoveOrdering {
+    pub fn new(max_ply: usize) -> Self {
+        Self {
+            killers: vec![[None; 2]; max_ply + 1],
+            history: [[[0; 64]; 6]; 2],
+            counter_moves: Box::new([[[None; 64]; 6]; 2]),
+            played: vec![None; max_ply + 1],
+        }
+    }
+
+    /// Records the move played at `ply`, or `None` for a null move.
+    pub fn set_played(&mut self, ply: usize, mv: Option<Move>) {
+        self.played[ply] = mv;
+    }
+
+    pub fn played(&self, ply: usize) -> Option<Move> {
+        self.played[ply]
+    }
+
+    pub fn sort(
+        &self,
+        mut moves: Vec<Move>,
+        pos: &Position,
+        ply: usize,
+        tt_move: Option<Move>,
+    ) -> Vec<Move> {
+        let color = pos.side_to_move();
+        let counter_move = self.counter_move(!color, ply);
+
+        moves.sort_by_cached_key(|mv| {
+            let score = if tt_move == Some(*mv) {
+                TT_MOVE_SCORE
+            } else if mv.flags().is_capture() {
+                let victim_value = piece_value(pos.victim_piece(mv).unwrap());
+                let attacker_value = piece_value(mv.piece());
+
+                CAPTURE_SCORE + victim_value * 10 - attacker_value
+            } else if self.killers[ply][0] == Some(*mv) {
+                KILLER_SCORES[0]
+            } else if self.killers[ply][1] == Some(*mv) {
+                KILLER_SCORES[1]
+            } else if counter_move == Some(*mv) {
+                COUNTER_MOVE_SCORE
+            } else {
+                self.history[color as usize][mv.piece() as usize][mv.to().index()]
+            };
+
+            -score
+        });
+
+        moves
+    }
+
+    /// Updates the quiet move tables after `mv` caused a beta cutoff,
+    /// penalizing the quiet moves that were tried before it.
+    pub fn update(&mut self, color: Color, ply: usize, depth: u8, mv: Move, tried: &[Move]) {
+        if self.killers[ply][0] != Some(mv) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(mv);
+        }
+
+        if let Some(previous) = ply.checked_sub(1).and_then(|p| self.played[p]) {
+            self.counter_moves[!color as usize][previous.piece() as usize][previous.to().index()] =
+                Some(mv);
+        }
+
+        let bonus = (depth as i32 * depth as i32).min(MAX_HISTORY / 4);
+
+        self.update_history(color, mv, bonus);
+
+        for &other in tried {
+            self.update_history(color, other, -bonus);
+        }
+    }
+
+    fn update_history(&mut self, color: Color, mv: Move, bonus: i32) {
+        let entry = &mut self.history[color as usize][mv.piece() as usize][mv.to().index()];
+
+        // gravity keeps entries within MAX_HISTORY and lets old values decay
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+
+    fn counter_move(&self, previous_color: Color, ply: usize) -> Option<Move> {
+        let previous = ply.checked_sub(1).and_then(|p| self.played[p])?;
+
+        self.counter_moves[previous_color as usize][previous.piece() as usize]
+            [previous.to().index()]
+    }
+}
+
+fn piece_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Rook => 4,
+        Piece::Queen => 5,
+        Piece::King => 99,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use psce_movegen::MoveGen;
+
+    use super::*;
+
+    fn moves(position: &Position, uci: &[&str]) -> Vec<Move> {
+        uci.iter()
+            .map(|mv| Move::from_uci(position, mv).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_killers() {
+        let position = Position::initial();
+        let [a, b, c] = moves(&position, &["g1f3", "b1c3", "e2e4"])[..] else {
+            unreachable!()
+        };
+        let mut ordering = MoveOrdering::new(8);
+
+        ordering.update(Color::White, 2, 1, a, &[]);
+        assert_eq!(ordering.killers[2], [Some(a), None]);
+
+        ordering.update(Color::White, 2, 1, b, &[]);
+        assert_eq!(ordering.killers[2], [Some(b), Some(a)]);
+
+        // a repeated cutoff does not push out the second killer
+        ordering.update(Color::White, 2, 1, b, &[]);
+        assert_eq!(ordering.killers[2], [Some(b), Some(a)]);
+
+        ordering.update(Color::White, 2, 1, c, &[]);
+        assert_eq!(ordering.killers[2], [Some(c), Some(b)]);
+        assert_eq!(ordering.killers[1], [None, None]);
+    }
+
+    #[test]
+    fn test_history_gravity() {
+        let position = Position::initial();
+        let [good, bad] = moves(&position, &["g1f3", "b1c3"])[..] else {
+            unreachable!()
+        };
+        let mut ordering = MoveOrdering::new(8);
+        let entry = |ordering: &MoveOrdering, mv: Move| {
+            ordering.history[Color::White as usize][mv.piece() as usize][mv.to().index()]
+        };
+
+        for _ in 0..1000 {
+            ordering.update(Color::White, 0, u8::MAX, good, &[bad]);
+
+            assert!(entry(&ordering, good) <= MAX_HISTORY);
+            assert!(entry(&ordering, bad) >= -MAX_HISTORY);
+        }
+
+        assert!(entry(&ordering, good) > MAX_HISTORY * 9 / 10);
+        assert!(entry(&ordering, bad) < -MAX_HISTORY * 9 / 10);
+
+        // a saturated entry still moves back when it stops cutting off
+        let before = entry(&ordering, good);
+        ordering.update(Color::White, 0, 4, bad, &[good]);
+        assert!(entry(&ordering, good) < before);
+    }
+
+    #[test]
+    fn test_counter_move() {
+        let mut position = Position::initial();
+        let previous = moves(&position, &["e2e4"])[0];
+        position.make_move(&previous);
+        let reply = moves(&position, &["d7d5"])[0];
+        let mut ordering = MoveOrdering::new(8);
+
+        ordering.set_played(0, Some(previous));
+        assert_eq!(ordering.counter_move(Color::White, 1), None);
+
+        ordering.update(Color::Black, 1, 3, reply, &[]);
+        assert_eq!(ordering.counter_move(Color::White, 1), Some(reply));
+
+        // counter-moves are keyed by the previous move, not the ply
+        ordering.set_played(4, Some(previous));
+        assert_eq!(ordering.counter_move(Color::White, 5), Some(reply));
+
+        ordering.set_played(4, None);
+        assert_eq!(ordering.counter_move(Color::White, 5), None);
+    }
+
+    #[test]
+    fn test_sort_order() {
+        let mut position = Position::initial();
+        position.make_move(&moves(&position, &["e2e4"])[0]);
+        let d5 = moves(&position, &["d7d5"])[0];
+        position.make_move(&d5);
+
+        let [
+            tt,
+            capture,
+            killer,
+            second_killer,
+            counter,
+            history,
+            penalized,
+        ] = moves(
+            &position,
+            &["a2a3", "e4d5", "g1f3", "b1c3", "f1b5", "d2d4", "h2h4"],
+        )[..]
+        else {
+            unreachable!()
+        };
+        let mut ordering = MoveOrdering::new(8);
+
+        ordering.set_played(0, Some(d5));
+        ordering.update(Color::White, 1, 1, second_killer, &[]);
+        ordering.update(Color::White, 1, 1, killer, &[]);
+
+        // a later cutoff after the same reply replaces the counter-move
+        ordering.set_played(2, Some(d5));
+        ordering.update(Color::White, 3, 1, counter, &[]);
+        ordering.update(Color::White, 6, 4, history, &[penalized]);
+
+        let sorted = ordering.sort(MoveGen::legals(&position), &position, 1, Some(tt));
+
+        assert_eq!(
+            sorted[..6],
+            [tt, capture, killer, second_killer, counter, history]
+        );
+        assert_eq!(sorted.last(), Some(&penalized));
+    }
+}
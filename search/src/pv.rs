@@ -1,40 +1,33 @@
 use psce_core::Move;
 
-const MAX_PLY: usize = 64;
-
+/// Triangular PV table with one line per ply up to the search's maximum
+/// ply.
 #[derive(Debug)]
 pub struct PrincipalVariations {
-    table: Box<[[Option<Move>; MAX_PLY]; MAX_PLY]>,
-    length: [usize; MAX_PLY],
+    lines: Vec<Vec<Move>>,
 }
 
 impl PrincipalVariations {
-    pub fn new() -> Self {
+    pub fn new(max_ply: usize) -> Self {
         Self {
-            table: Box::new([[None; MAX_PLY]; MAX_PLY]),
-            length: [0; MAX_PLY],
+            lines: vec![Vec::new(); max_ply + 1],
         }
     }
 
     pub fn update(&mut self, ply: usize, mv: Move) {
-        let child_length = self.length[ply + 1];
-
-        let (left, right) = self.table.split_at_mut(ply + 1);
-
-        left[ply][0] = Some(mv);
-        left[ply][1..=child_length].copy_from_slice(&right[0][0..child_length]);
+        let (left, right) = self.lines.split_at_mut(ply + 1);
+        let line = &mut left[ply];
 
-        self.length[ply] = child_length + 1;
+        line.clear();
+        line.push(mv);
+        line.extend_from_slice(&right[0]);
     }
 
     pub fn clear_ply(&mut self, ply: usize) {
-        self.length[ply] = 0;
+        self.lines[ply].clear();
     }
 
     pub fn get_pv(&self, ply: usize) -> Vec<Move> {
-        self.table[ply][0..self.length[ply]]
-            .iter()
-            .map(|mv| mv.unwrap())
-            .collect()
+        self.lines[ply].clone()
     }
 }
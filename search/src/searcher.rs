@@ -0,0 +1,703 @@
+use std::{
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+use psce_core::{Color, Move, Piece, Position, Rank, Variant};
+use psce_movegen::MoveGen;
+
+use crate::{
+    CHECKMATE_SCORE, MAX_PLY, PvLine, SearchOptions, SearchStats,
+    eval::{evaluate_position, is_mate_score},
+    nnue::AccumulatorStack,
+    ordering::MoveOrdering,
+    pv::PrincipalVariations,
+    tablebase,
+    tt::{Bound, Entry, TranspositionTable},
+};
+
+const NULL_MOVE_MIN_DEPTH: u8 = 3;
+const NULL_MOVE_REDUCTION: u8 = 2;
+const LMR_MIN_DEPTH: u8 = 3;
+const LMR_MIN_MOVES: usize = 3;
+const REVERSE_FUTILITY_MAX_DEPTH: u8 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 80;
+const FUTILITY_MAX_DEPTH: u8 = 2;
+const FUTILITY_MARGIN: i32 = 150;
+const ASPIRATION_MIN_DEPTH: u8 = 4;
+const ASPIRATION_WINDOW: i32 = 25;
+const TIME_CHECK_INTERVAL: u64 = 1024;
+const MAX_LINE_EXTENSIONS: u8 = 16;
+const SINGULAR_MIN_DEPTH: u8 = 8;
+const SINGULAR_TT_DEPTH_MARGIN: u8 = 3;
+const SINGULAR_MARGIN: i32 = 2;
+
+/// State shared between all threads of a search.
+pub struct Shared {
+    pub tt: Arc<TranspositionTable>,
+    pub stop: AtomicBool,
+    pub nodes: AtomicU64,
+    pub start: Instant,
+    /// When pondering ended, from which the time limit counts.
+    pub ponderhit: OnceLock<Instant>,
+}
+
+pub struct IterationResult {
+    pub depth: u8,
+    pub lines: Vec<PvLine>,
+}
+
+pub struct Searcher<'a> {
+    options: &'a SearchOptions,
+    shared: &'a Shared,
+    pvs: PrincipalVariations,
+    ordering: MoveOrdering,
+    pub stats: SearchStats,
+    accumulators: Option<AccumulatorStack>,
+    /// Legal root moves, restricted to `options.search_moves` if given.
+    root_moves: Vec<Move>,
+    /// Root moves already reported as a PV line in the current iteration.
+    excluded: Vec<Move>,
+    /// Extensions applied on the line being searched.
+    extensions: u8,
+    /// Move skipped at each ply while verifying that it is singular.
+    singular: Vec<Option<Move>>,
+    /// Nodes at this ply return their static evaluation.
+    max_ply: usize,
+    completed_depth: u8,
+    aborted: bool,
+}
+
+impl<'a> Searcher<'a> {
+    pub fn new(pos: &Position, options: &'a SearchOptions, shared: &'a Shared) -> Self {
+        let max_ply = options.max_ply.clamp(1, MAX_PLY);
+
+        Self {
+            options,
+            shared,
+            pvs: PrincipalVariations::new(max_ply),
+            ordering: MoveOrdering::new(max_ply),
+            stats: SearchStats::default(),
+            accumulators: options
+                .network
+                .as_ref()
+                .map(|network| AccumulatorStack::new(network, pos)),
+            root_moves: MoveGen::legals(pos)
+                .into_iter()
+                .filter(|mv| options.search_moves.is_empty() || options.search_moves.contains(mv))
+                .collect(),
+            excluded: Vec::new(),
+            extensions: 0,
+            singular: vec![None; max_ply + 1],
+            max_ply,
+            completed_depth: 0,
+            aborted: false,
+        }
+    }
+
+    /// Runs iterative deepening until the depth limit is reached or the
+    /// search is stopped. Helper threads with an odd `thread_id` search one
+    /// ply deeper than the iteration so threads diverge.
+    ///
+    /// Each iteration searches `multi_pv` lines, excluding the root moves of
+    /// the lines found before it.
+    pub fn iterate(&mut self, pos: &mut Position, thread_id: usize) -> Option<IterationResult> {
+        let multi_pv = if thread_id == 0 {
+            self.options.multi_pv.clamp(1, self.root_moves.len().max(1))
+        } else {
+            1
+        };
+
+        let mut best: Option<IterationResult> = None;
+
+        'iterations: for iteration in 1..=self.options.depth {
+            let depth = iteration
+                .saturating_add((thread_id % 2) as u8)
+                .min(self.options.depth);
+
+            if best.as_ref().is_some_and(|b| b.depth >= depth) {
+                continue;
+            }
+
+            let mut lines = Vec::with_capacity(multi_pv);
+            self.excluded.clear();
+
+            for index in 0..multi_pv {
+                let previous_score = best
+                    .as_ref()
+                    .and_then(|b| b.lines.get(index))
+                    .map(|line| line.score);
+                let (score, bound) = self.aspiration_search(pos, depth, previous_score);
+
+                if self.aborted {
+                    break 'iterations;
+                }
+
+                let pv = self.pvs.get_pv(0);
+
+                let Some(&root_move) = pv.first() else {
+                    break 'iterations;
+                };
+
+                self.excluded.push(root_move);
+                lines.push(PvLine { score, bound, pv });
+            }
+
+            lines.sort_by_key(|line| -line.score);
+
+            best = Some(IterationResult { depth, lines });
+            self.completed_depth = depth;
+        }
+
+        best
+    }
+
+    fn should_abort(&self) -> bool {
+        // a search always completes its first iteration so it has a move
+        if self.completed_depth == 0 {
+            return false;
+        }
+
+        if self.shared.stop.load(Ordering::Relaxed) || self.options.is_stopped() {
+            return true;
+        }
+
+        if self.options.is_pondering() {
+            return false;
+        }
+
+        if self
+            .options
+            .nodes
+            .is_some_and(|nodes| self.shared.nodes.load(Ordering::Relaxed) > nodes)
+        {
+            return true;
+        }
+
+        self.stats.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+            && self
+                .options
+                .movetime
+                .is_some_and(|movetime| self.clock_start().elapsed() >= movetime)
+    }
+
+    fn clock_start(&self) -> Instant {
+        if self.options.ponder.is_some() {
+            *self.shared.ponderhit.get_or_init(Instant::now)
+        } else {
+            self.shared.start
+        }
+    }
+
+    /// Static evaluation from the point of view of the side to move.
+    fn evaluate(&self, pos: &Position) -> i32 {
+        if let (Some(network), Some(accumulators)) = (&self.options.network, &self.accumulators) {
+            return accumulators.evaluate(network, pos.side_to_move());
+        }
+
+        if pos.side_to_move() == Color::White {
+            evaluate_position(pos)
+        } else {
+            -evaluate_position(pos)
+        }
+    }
+
+    /// Searches the root with a narrow window around the previous
+    /// iteration's score, widening it on fail-high and fail-low.
+    fn aspiration_search(
+        &mut self,
+        pos: &mut Position,
+        depth: u8,
+        previous: Option<i32>,
+    ) -> (i32, Bound) {
+        let Some(previous) = previous.filter(|_| depth >= ASPIRATION_MIN_DEPTH) else {
+            let score = self.negamax(pos, depth, 0, -CHECKMATE_SCORE, CHECKMATE_SCORE);
+
+            return (score, Bound::Exact);
+        };
+
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = (previous - delta).max(-CHECKMATE_SCORE);
+        let mut beta = (previous + delta).min(CHECKMATE_SCORE);
+
+        loop {
+            let score = self.negamax(pos, depth, 0, alpha, beta);
+
+            let bound = if score <= alpha {
+                Bound::Upper
+            } else if score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+
+            if self.aborted {
+                return (score, bound);
+            }
+
+            if bound == Bound::Upper && alpha > -CHECKMATE_SCORE {
+                alpha = (alpha - delta).max(-CHECKMATE_SCORE);
+            } else if bound == Bound::Lower && beta < CHECKMATE_SCORE {
+                beta = (beta + delta).min(CHECKMATE_SCORE);
+            } else {
+                return (score, bound);
+            }
+
+            self.stats.aspiration_researches += 1;
+            delta *= 2;
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        pos: &mut Position,
+        depth: u8,
+        ply: usize,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> i32 {
+        self.stats.nodes += 1;
+        self.shared.nodes.fetch_add(1, Ordering::Relaxed);
+
+        if !self.aborted && self.should_abort() {
+            self.aborted = true;
+        }
+
+        if self.aborted {
+            return 0;
+        }
+
+        self.stats.seldepth = self.stats.seldepth.max(ply);
+        self.pvs.clear_ply(ply);
+
+        // extended lines are cut off at the maximum ply
+        if ply >= self.max_ply {
+            return self.evaluate(pos);
+        }
+
+        // checked ahead of mate distance pruning, as antichess wins come
+        // a ply earlier than a mate would
+        if let Some(winner) = pos.variant_winner() {
+            return if winner == pos.side_to_move() {
+                CHECKMATE_SCORE - ply as i32
+            } else {
+                -CHECKMATE_SCORE + ply as i32
+            };
+        }
+
+        if ply > 0 {
+            // mate distance pruning: no line from here can beat a shorter mate
+            alpha = alpha.max(-CHECKMATE_SCORE + ply as i32);
+            beta = beta.min(CHECKMATE_SCORE - ply as i32 - 1);
+
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        if depth == 0 {
+            return self.evaluate(pos);
+        }
+
+        let color = pos.side_to_move();
+        let pruning = self.options.pruning;
+        let is_pv_node = beta - alpha > 1;
+        let original_alpha = alpha;
+        let singular_move = self.singular[ply];
+
+        let tt_entry = self.shared.tt.probe(pos.hash(), ply);
+
+        if let Some(entry) = tt_entry
+            && singular_move.is_none()
+            && !is_pv_node
+            && ply > 0
+            && entry.depth >= depth
+        {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+
+            if cutoff {
+                return entry.score;
+            }
+        }
+
+        // probing right after zeroing moves keeps the 50-move counter exact
+        if let Some(tablebase) = &self.options.tablebase
+            && ply > 0
+            && pos.halfmove_clock() == 0
+            && tablebase::can_probe(tablebase.as_ref(), pos)
+            && let Some(wdl) = tablebase.probe_wdl(pos)
+        {
+            self.stats.tb_hits += 1;
+
+            return tablebase::score(wdl, ply);
+        }
+
+        let in_check = is_in_check(pos);
+        let can_prune = !in_check && !is_pv_node && ply > 0;
+        let static_eval = self.evaluate(pos);
+
+        // a static margin says nothing about mates found on other lines
+        if pruning.reverse_futility
+            && can_prune
+            && !is_mate_score(beta)
+            && depth <= REVERSE_FUTILITY_MAX_DEPTH
+            && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+        {
+            return static_eval;
+        }
+
+        if pruning.null_move
+            && can_prune
+            && singular_move.is_none()
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && static_eval >= beta
+            && has_non_pawn_material(pos, color)
+            && pos.variant() != Variant::Antichess
+        {
+            let reduction = NULL_MOVE_REDUCTION + depth / 4;
+
+            let undo = pos.make_null_move();
+            self.ordering.set_played(ply, None);
+            let score = -self.negamax(
+                pos,
+                depth.saturating_sub(1 + reduction),
+                ply + 1,
+                -beta,
+                -beta + 1,
+            );
+            pos.undo_null_move(&undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+        }
+
+        let futility_pruning = pruning.futility
+            && can_prune
+            && depth <= FUTILITY_MAX_DEPTH
+            && static_eval + FUTILITY_MARGIN * depth as i32 <= alpha;
+
+        let mut best = -CHECKMATE_SCORE;
+        let mut best_move = None;
+        let mut moves_searched = 0;
+        let mut quiets_tried = Vec::new();
+
+        // the entry may be from another position with the same hash
+        let tt_move = tt_entry
+            .and_then(|entry| entry.mv)
+            .filter(|&mv| pos.is_pseudo_legal(mv))
+            .and_then(|mv| mv.unpack(pos));
+
+        let moves = self
+            .ordering
+            .sort(MoveGen::pseudo_legals(pos), pos, ply, tt_move);
+
+        let singular_extension = match (tt_entry, tt_move) {
+            (Some(entry), Some(tt_move)) if ply > 0 && singular_move.is_none() => {
+                self.singular_extension(pos, tt_move, entry, depth, ply)
+            }
+            _ => None,
+        };
+
+        if self.aborted {
+            return 0;
+        }
+
+        for mv in moves {
+            if ply == 0 && (!self.root_moves.contains(&mv) || self.excluded.contains(&mv)) {
+                continue;
+            }
+
+            if singular_move == Some(mv) {
+                continue;
+            }
+
+            let is_recapture = mv.flags().is_capture()
+                && ply > 0
+                && self.ordering.played(ply - 1).is_some_and(|previous| {
+                    previous.flags().is_capture() && previous.to() == mv.to()
+                });
+
+            let is_quiet = mv.is_quiet();
+            let gives_check = MoveGen::gives_check(pos, &mv);
+
+            let undo = pos.make_move(&mv);
+
+            if !MoveGen::leaves_king_safe(pos, color) {
+                pos.undo_move(&mv, &undo);
+                continue;
+            }
+
+            if futility_pruning && moves_searched > 0 && is_quiet && !gives_check {
+                pos.undo_move(&mv, &undo);
+                continue;
+            }
+
+            if let (Some(network), Some(accumulators)) =
+                (&self.options.network, &mut self.accumulators)
+            {
+                accumulators.make_move(network, &mv, color, undo.captured_piece, &undo.exploded);
+            }
+
+            self.ordering.set_played(ply, Some(mv));
+
+            // recaptures and pawn pushes are only extended on the PV
+            let extend = self.extensions < MAX_LINE_EXTENSIONS
+                && (gives_check
+                    || singular_extension == Some(mv)
+                    || (is_pv_node && (is_recapture || is_passed_pawn_push(pos, &mv, color))));
+            let new_depth = depth - 1 + extend as u8;
+
+            self.extensions += extend as u8;
+
+            let score = if moves_searched == 0 {
+                -self.negamax(pos, new_depth, ply + 1, -beta, -alpha)
+            } else {
+                let reduction = if pruning.late_move_reductions
+                    && depth >= LMR_MIN_DEPTH
+                    && moves_searched >= LMR_MIN_MOVES
+                    && is_quiet
+                    && !in_check
+                    && !gives_check
+                {
+                    (1 + moves_searched / 8).min(depth as usize - 2) as u8
+                } else {
+                    0
+                };
+
+                let narrow_alpha = if pruning.principal_variation_search {
+                    -alpha - 1
+                } else {
+                    -beta
+                };
+
+                let mut score =
+                    -self.negamax(pos, new_depth - reduction, ply + 1, narrow_alpha, -alpha);
+
+                if reduction > 0 && score > alpha {
+                    score = -self.negamax(pos, new_depth, ply + 1, narrow_alpha, -alpha);
+                }
+
+                if pruning.principal_variation_search && score > alpha && score < beta {
+                    score = -self.negamax(pos, new_depth, ply + 1, -beta, -alpha);
+                }
+
+                score
+            };
+
+            self.extensions -= extend as u8;
+
+            moves_searched += 1;
+
+            pos.undo_move(&mv, &undo);
+
+            if let Some(accumulators) = &mut self.accumulators {
+                accumulators.undo_move();
+            }
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(mv);
+
+                self.pvs.update(ply, mv);
+
+                if score > alpha {
+                    alpha = score;
+                }
+            }
+
+            if alpha >= beta {
+                self.stats.beta_cutoffs += 1;
+
+                if moves_searched == 1 {
+                    self.stats.first_move_cutoffs += 1;
+                }
+
+                if is_quiet {
+                    self.ordering.update(color, ply, depth, mv, &quiets_tried);
+                }
+
+                break;
+            }
+
+            if is_quiet {
+                quiets_tried.push(mv);
+            }
+        }
+
+        // only the skipped move was legal, which makes it singular
+        if moves_searched == 0 && singular_move.is_some() {
+            return alpha;
+        }
+
+        // an antichess player without a move has won
+        if moves_searched == 0 {
+            return if pos.variant() == Variant::Antichess {
+                CHECKMATE_SCORE - ply as i32
+            } else if in_check {
+                -CHECKMATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        // a search with excluded moves does not describe the position
+        if (ply == 0 && !self.excluded.is_empty()) || singular_move.is_some() {
+            return best;
+        }
+
+        self.shared.tt.store(
+            pos.hash(),
+            ply,
+            Entry {
+                mv: best_move.as_ref().map(Move::pack),
+                score: best,
+                depth,
+                bound,
+            },
+        );
+
+        best
+    }
+
+    /// Returns the transposition table move if every other move fails low
+    /// against a margin below its score in a reduced search.
+    fn singular_extension(
+        &mut self,
+        pos: &mut Position,
+        tt_move: Move,
+        entry: Entry,
+        depth: u8,
+        ply: usize,
+    ) -> Option<Move> {
+        if depth < SINGULAR_MIN_DEPTH
+            || entry.depth + SINGULAR_TT_DEPTH_MARGIN < depth
+            || entry.bound == Bound::Upper
+            || is_mate_score(entry.score)
+        {
+            return None;
+        }
+
+        let singular_beta = entry.score - SINGULAR_MARGIN * depth as i32;
+
+        self.singular[ply] = Some(tt_move);
+        let score = self.negamax(pos, depth / 2, ply, singular_beta - 1, singular_beta);
+        self.singular[ply] = None;
+
+        (score < singular_beta).then_some(tt_move)
+    }
+}
+
+/// Whether `mv`, already made in `pos`, pushes a pawn without enemy pawns in
+/// front of it on its own or the adjacent files to the sixth rank or further.
+fn is_passed_pawn_push(pos: &Position, mv: &Move, color: Color) -> bool {
+    if mv.piece() != Piece::Pawn {
+        return false;
+    }
+
+    let (rank, file) = (mv.to().rank(), mv.to().file());
+    let relative_rank = match color {
+        Color::White => rank,
+        Color::Black => rank.flip(),
+    };
+
+    relative_rank >= Rank::Sixth
+        && !pos
+            .bitboards()
+            .piece(!color, Piece::Pawn)
+            .squares()
+            .any(|sq| {
+                let ahead = match color {
+                    Color::White => sq.rank() > rank,
+                    Color::Black => sq.rank() < rank,
+                };
+
+                ahead && sq.file().distance(file) <= 1
+            })
+}
+
+fn is_in_check(pos: &Position) -> bool {
+    MoveGen::is_in_check(pos, pos.side_to_move())
+}
+
+/// Guards null move pruning against zugzwang in pawn-only endgames.
+fn has_non_pawn_material(pos: &Position, color: Color) -> bool {
+    [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+        .iter()
+        .any(|&piece| pos.bitboards().piece(color, piece).is_not_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared() -> Shared {
+        Shared {
+            tt: Arc::new(TranspositionTable::new(1)),
+            stop: AtomicBool::new(false),
+            nodes: AtomicU64::new(0),
+            start: Instant::now(),
+            ponderhit: OnceLock::new(),
+        }
+    }
+
+    #[test]
+    fn test_max_ply_returns_static_eval() {
+        let mut pos = Position::from_fen("4k3/8/8/8/8/8/4Q3/4K3 b - - 0 1").unwrap();
+        let options = SearchOptions {
+            max_ply: 8,
+            ..Default::default()
+        };
+        let shared = shared();
+        let mut searcher = Searcher::new(&pos, &options, &shared);
+        searcher.completed_depth = 1;
+
+        let score = searcher.negamax(&mut pos, 4, 8, -CHECKMATE_SCORE, CHECKMATE_SCORE);
+
+        assert_eq!(score, searcher.evaluate(&pos));
+        assert_eq!(searcher.stats.seldepth, 8);
+    }
+
+    #[test]
+    fn test_passed_pawn_push() {
+        let push = |fen: &str, uci: &str| {
+            let mut pos = Position::from_fen(fen).unwrap();
+            let mv = MoveGen::legals(&pos)
+                .into_iter()
+                .find(|mv| mv.to_string() == uci)
+                .unwrap();
+            let color = pos.side_to_move();
+            pos.make_move(&mv);
+
+            is_passed_pawn_push(&pos, &mv, color)
+        };
+
+        assert!(push("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", "d5d6"));
+        assert!(!push("4k3/2p5/8/3P4/8/8/8/4K3 w - - 0 1", "d5d6"));
+        assert!(!push("4k3/8/8/8/3P4/8/8/4K3 w - - 0 1", "d4d5"));
+        assert!(push("4k3/8/8/8/8/3p4/8/4K3 b - - 0 1", "d3d2"));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::PvLine;
+
+pub const MAX_SKILL_LEVEL: u8 = 20;
+
+/// Rating of every limited skill level, measured with the match runner
+/// between neighbouring levels (`bench match --depth 64 --skill-a 1
+/// --skill-b 0`): 100 games per step up to level 12, 20 games above. The
+/// steps are summed from level 0, anchored at 500. Steps measured below zero
+/// are within the noise and count as zero.
+const ELO_BY_LEVEL: [u32; MAX_SKILL_LEVEL as usize] = [
+    500, 556, 1132, 1139, 1589, 1630, 1994, 2029, 2323, 2323, 2514, 2549, 2688, 2688, 2835, 2943,
+    3111, 3146, 3293, 3293,
+];
+
+pub const MIN_ELO: u32 = ELO_BY_LEVEL[0];
+pub const MAX_ELO: u32 = ELO_BY_LEVEL[MAX_SKILL_LEVEL as usize - 1];
+
+/// Number of root moves a limited search considers playing.
+pub(crate) const CANDIDATES: usize = 6;
+
+const PAWN_SCORE: i32 = 100;
+
+/// Playing strength from 0 to `MAX_SKILL_LEVEL`, which is full strength.
+/// Lower levels search shallower and fewer nodes and pick among the best
+/// root moves at random, more often the weaker ones.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Skill {
+    level: u8,
+}
+
+impl Skill {
+    pub fn new(level: u8) -> Self {
+        Self {
+            level: level.min(MAX_SKILL_LEVEL),
+        }
+    }
+
+    /// The strongest level rated at most `elo`, as for `UCI_Elo`.
+    pub fn from_elo(elo: u32) -> Self {
+        let level = ELO_BY_LEVEL
+            .iter()
+            .rposition(|&rating| rating <= elo)
+            .unwrap_or(0);
+
+        Self::new(level as u8)
+    }
+
+    pub fn level(self) -> u8 {
+        self.level
+    }
+
+    pub fn is_limited(self) -> bool {
+        self.level < MAX_SKILL_LEVEL
+    }
+
+    pub fn depth(self) -> u8 {
+        1 + self.level / 2
+    }
+
+    pub fn nodes(self) -> u64 {
+        1_000 << self.level
+    }
+
+    /// Picks the index of the line to play from `lines`, sorted by score.
+    /// Each line gets a bonus that grows with its distance to the best line
+    /// and a random part bounded by the spread of the scores.
+    pub(crate) fn pick(self, lines: &[PvLine], rng: &mut fastrand::Rng) -> usize {
+        let Some(top) = lines.first().map(|line| line.score) else {
+            return 0;
+        };
+
+        let spread = (top - lines[lines.len() - 1].score).min(2 * PAWN_SCORE);
+        let weakness = 120 - 6 * self.level as i32;
+
+        (0..lines.len())
+            .max_by_key(|&index| {
+                let score = lines[index].score;
+                let push = (weakness * (top - score) + spread * rng.i32(0..weakness)) / 128;
+
+                (score + push, -(index as i32))
+            })
+            .unwrap()
+    }
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self::new(MAX_SKILL_LEVEL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bound;
+
+    fn lines(scores: &[i32]) -> Vec<PvLine> {
+        scores
+            .iter()
+            .map(|&score| PvLine {
+                score,
+                bound: Bound::Exact,
+                pv: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_elo() {
+        assert!(ELO_BY_LEVEL.is_sorted());
+        assert_eq!(Skill::from_elo(0).level(), 0);
+        assert_eq!(Skill::from_elo(MIN_ELO).level(), 0);
+        assert_eq!(Skill::from_elo(1_200).level(), 3);
+        // levels measured equally strong resolve to the stronger one
+        assert_eq!(Skill::from_elo(2_323).level(), 9);
+        assert_eq!(Skill::from_elo(MAX_ELO).level(), MAX_SKILL_LEVEL - 1);
+        assert_eq!(Skill::from_elo(u32::MAX).level(), MAX_SKILL_LEVEL - 1);
+        assert!(Skill::from_elo(MAX_ELO).is_limited());
+        assert!(!Skill::default().is_limited());
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut rng = fastrand::Rng::with_seed(1);
+        let lines = lines(&[50, 40, 20, -900]);
+
+        let mut picks = |level| -> Vec<_> {
+            (0..200)
+                .map(|_| Skill::new(level).pick(&lines, &mut rng))
+                .collect()
+        };
+
+        assert!(picks(0).iter().any(|&index| index > 0));
+        // stronger levels never play moves far behind the best
+        assert!(picks(19).iter().all(|&index| index < 3));
+
+        assert_eq!(Skill::new(0).pick(&lines[..1], &mut rng), 0);
+    }
+}
@@ -0,0 +1,1371 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{LazyLock, OnceLock},
+};
+
+use psce_core::{Color, Piece, Position};
+use psce_movegen::MoveGen;
+
+use crate::tablebase::{Tablebase, Wdl, can_probe};
+
+#[cfg(test)]
+mod writer;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+/// Flags of a compressed table.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+/// Flags in the first byte of a file.
+const FILE_SPLIT: u8 = 1;
+const FILE_HAS_PAWNS: u8 = 2;
+
+const MAX_PIECES: usize = 7;
+
+/// Symbol marking a leaf of the pairing tree.
+const LEAF: u16 = 0xFFF;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Kind {
+    Wdl,
+    Dtz,
+}
+
+/// Syzygy tablebase files found in one or more directories.
+///
+/// Tables are discovered by name (`KRvKB.rtbw`, `KRvKB.rtbz`) and checked
+/// for the Syzygy magic bytes. A table is read into memory the first time
+/// it is probed.
+#[derive(Debug)]
+pub struct Syzygy {
+    wdl: HashMap<String, Entry>,
+    dtz: HashMap<String, Entry>,
+    max_pieces: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    path: PathBuf,
+    table: OnceLock<Option<Table>>,
+}
+
+/// Result of looking up a position in a single table.
+enum Lookup {
+    Value(i32),
+    /// DTZ tables only store one side to move.
+    ChangeSideToMove,
+}
+
+impl Syzygy {
+    /// Opens the tables in `paths`, a list of directories separated by `:`
+    /// (or `;` on Windows) as in the UCI `SyzygyPath` option.
+    pub fn open(paths: &str) -> Result<Self, String> {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+
+        let mut syzygy = Self {
+            wdl: HashMap::new(),
+            dtz: HashMap::new(),
+            max_pieces: 0,
+        };
+
+        for dir in paths.split(separator).filter(|p| !p.is_empty()) {
+            let entries = fs::read_dir(dir)
+                .map_err(|e| format!("Failed to read tablebase directory {}: {}", dir, e))?;
+
+            for entry in entries.flatten() {
+                syzygy.add(&entry.path());
+            }
+        }
+
+        Ok(syzygy)
+    }
+
+    fn add(&mut self, path: &Path) {
+        let (Some(material), Some(extension)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.extension().and_then(|s| s.to_str()),
+        ) else {
+            return;
+        };
+
+        let (tables, magic) = match extension {
+            "rtbw" => (&mut self.wdl, WDL_MAGIC),
+            "rtbz" => (&mut self.dtz, DTZ_MAGIC),
+            _ => return,
+        };
+
+        let Some(pieces) = piece_count(material) else {
+            return;
+        };
+
+        let mut header = [0; 4];
+        let has_magic = File::open(path)
+            .and_then(|mut file| file.read_exact(&mut header))
+            .is_ok_and(|()| header == magic);
+
+        if has_magic {
+            let entry = Entry {
+                path: path.to_path_buf(),
+                table: OnceLock::new(),
+            };
+
+            tables.insert(material.to_string(), entry);
+            self.max_pieces = self.max_pieces.max(pieces);
+        }
+    }
+
+    /// Number of WDL and DTZ tables found.
+    pub fn tables(&self) -> (usize, usize) {
+        (self.wdl.len(), self.dtz.len())
+    }
+
+    fn table(&self, kind: Kind, material: &str) -> Option<&Table> {
+        let tables = match kind {
+            Kind::Wdl => &self.wdl,
+            Kind::Dtz => &self.dtz,
+        };
+
+        let entry = tables.get(material)?;
+
+        entry
+            .table
+            .get_or_init(|| Table::load(&entry.path, material, kind))
+            .as_ref()
+    }
+
+    /// Looks up `pos` in the table for its material. `wdl` is needed to
+    /// decode DTZ values.
+    fn lookup(&self, pos: &Position, kind: Kind, wdl: i32) -> Option<Lookup> {
+        let white = side_material(pos, Color::White);
+        let black = side_material(pos, Color::Black);
+
+        if white.len() + black.len() == 2 {
+            return Some(Lookup::Value(0));
+        }
+
+        // tables are named with the stronger side first, which may be black
+        let (table, flip) = match self.table(kind, &format!("{}v{}", white, black)) {
+            Some(table) => (table, false),
+            None => (self.table(kind, &format!("{}v{}", black, white))?, true),
+        };
+
+        let (side, file, index) = table.encode(pos, flip)?;
+        let pairs = table.pairs(side, file);
+
+        // pawnless symmetric tables store both sides as white to move
+        let one_side = table.has_pawns || !table.symmetric;
+
+        if kind == Kind::Dtz && one_side && (pairs.flags & FLAG_STM) as usize != side {
+            return Some(Lookup::ChangeSideToMove);
+        }
+
+        let value = table.decompress(pairs, index)?;
+
+        Some(Lookup::Value(match kind {
+            Kind::Wdl => value as i32 - 2,
+            Kind::Dtz => table.map_dtz(file, value, wdl)?,
+        }))
+    }
+
+    /// WDL of `pos` from -2 (loss) to 2 (win), and whether a zeroing move is
+    /// best.
+    ///
+    /// The tables store arbitrary values for positions where a capture is
+    /// best, so captures (and with `zeroing` set, pawn moves) are searched
+    /// first.
+    fn search(&self, pos: &Position, zeroing: bool) -> Option<(i32, bool)> {
+        let moves = MoveGen::legals(pos);
+        let mut best = -2;
+        let mut searched = 0;
+
+        for mv in &moves {
+            let pawn_move = zeroing && mv.piece() == Piece::Pawn;
+
+            if !mv.flags().is_capture() && !pawn_move {
+                continue;
+            }
+
+            searched += 1;
+
+            let mut child = pos.clone();
+            child.make_move(mv);
+
+            let value = -self.search(&child, false)?.0;
+
+            if value > best {
+                best = value;
+
+                if value == 2 {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        // the stored value is wrong when every move was searched, for
+        // instance if the only moves are en passant captures
+        let all_searched = searched > 0 && searched == moves.len();
+
+        let value = if all_searched {
+            best
+        } else {
+            match self.lookup(pos, Kind::Wdl, 0)? {
+                Lookup::Value(value) => value,
+                Lookup::ChangeSideToMove => return None,
+            }
+        };
+
+        if best >= value {
+            return Some((best, best > 0 || all_searched));
+        }
+
+        Some((value, false))
+    }
+
+    fn dtz(&self, pos: &Position) -> Option<i32> {
+        let (wdl, zeroing) = self.search(pos, true)?;
+
+        if wdl == 0 {
+            return Some(0);
+        }
+
+        if zeroing {
+            return Some(dtz_before_zeroing(wdl));
+        }
+
+        if let Lookup::Value(dtz) = self.lookup(pos, Kind::Dtz, wdl)? {
+            let cursed = if wdl.abs() == 1 { 100 } else { 0 };
+
+            return Some((dtz + cursed) * wdl.signum());
+        }
+
+        // the table stores the other side to move, so find the best DTZ one
+        // ply deeper
+        let mut best = None;
+
+        for mv in MoveGen::legals(pos) {
+            let zeroing = mv.flags().is_capture() || mv.piece() == Piece::Pawn;
+
+            let mut child = pos.clone();
+            child.make_move(&mv);
+
+            let mut dtz = if zeroing {
+                // the DTZ of the move itself, counted before it zeroes
+                -dtz_before_zeroing(self.search(&child, false)?.0)
+            } else {
+                -self.dtz(&child)?
+            };
+
+            if dtz == 1
+                && MoveGen::is_in_check(&child, child.side_to_move())
+                && MoveGen::legals(&child).is_empty()
+            {
+                best = Some(1);
+            }
+
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+
+            if dtz.signum() == wdl.signum() && best.is_none_or(|best| dtz < best) {
+                best = Some(dtz);
+            }
+        }
+
+        // without a legal move the side to move is mated
+        Some(best.unwrap_or(-1))
+    }
+}
+
+impl Tablebase for Syzygy {
+    fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    fn probe_wdl(&self, pos: &Position) -> Option<Wdl> {
+        if !can_probe(self, pos) {
+            return None;
+        }
+
+        Some(match self.search(pos, false)?.0 {
+            -2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            1 => Wdl::CursedWin,
+            2 => Wdl::Win,
+            _ => Wdl::Draw,
+        })
+    }
+
+    fn probe_dtz(&self, pos: &Position) -> Option<i32> {
+        if !can_probe(self, pos) {
+            return None;
+        }
+
+        self.dtz(pos)
+    }
+}
+
+/// DTZ of the position before a zeroing move with the given result.
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+/// Counts the pieces in a table name like `KRvKB`, rejecting other files.
+fn piece_count(material: &str) -> Option<usize> {
+    let (white, black) = material.split_once('v')?;
+
+    [white, black]
+        .iter()
+        .all(|side| side.starts_with('K') && side.chars().all(|c| "KQRBNP".contains(c)))
+        .then_some(white.len() + black.len())
+}
+
+/// The pieces of `color` as in a table name, like `KRB`.
+fn side_material(pos: &Position, color: Color) -> String {
+    [
+        (Piece::King, 'K'),
+        (Piece::Queen, 'Q'),
+        (Piece::Rook, 'R'),
+        (Piece::Bishop, 'B'),
+        (Piece::Knight, 'N'),
+        (Piece::Pawn, 'P'),
+    ]
+    .iter()
+    .flat_map(|&(piece, c)| {
+        let count = pos.bitboards().piece(color, piece).count() as usize;
+        std::iter::repeat_n(c, count)
+    })
+    .collect()
+}
+
+/// Piece code used in the files: 1 to 6 from pawn to king, plus 8 for
+/// black.
+fn piece_code(color: Color, piece: Piece) -> u8 {
+    let code = match piece {
+        Piece::Pawn => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Rook => 4,
+        Piece::Queen => 5,
+        Piece::King => 6,
+    };
+
+    match color {
+        Color::White => code,
+        Color::Black => code | 8,
+    }
+}
+
+/// Ranks above the a1-h8 diagonal are positive, below are negative.
+fn off_diagonal(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+/// Tables for the position encoding, shared by every file.
+struct Encoding {
+    /// Squares below the a1-h8 diagonal to 0..28.
+    map_b1h1h7: [u64; 64],
+    /// Squares of the a1-d1-d4 triangle to 0..10, diagonal last.
+    map_a1d1d4: [u64; 64],
+    /// The 462 placements of two kings, the first in the a1-d1-d4
+    /// triangle.
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 6],
+    /// Pawn squares to 0..48, the leading pawn having the highest value.
+    map_pawns: [usize; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+static ENCODING: LazyLock<Encoding> = LazyLock::new(Encoding::new);
+
+impl Encoding {
+    fn new() -> Self {
+        let mut encoding = Self {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let below: Vec<_> = (0..64).filter(|&sq| off_diagonal(sq) < 0).collect();
+
+        for (code, &sq) in below.iter().enumerate() {
+            encoding.map_b1h1h7[sq] = code as u64;
+        }
+
+        let triangle = (0..64).filter(|&sq| sq % 8 <= 3 && sq / 8 <= 3);
+        let (diagonal, below): (Vec<_>, Vec<_>) = triangle
+            .filter(|&sq| off_diagonal(sq) <= 0)
+            .partition(|&sq| off_diagonal(sq) == 0);
+
+        for (code, &sq) in below.iter().chain(&diagonal).enumerate() {
+            encoding.map_a1d1d4[sq] = code as u64;
+        }
+
+        let mut code = 0;
+        let mut both_on_diagonal = Vec::new();
+
+        for (idx, &s1) in below.iter().chain(&diagonal).enumerate() {
+            for s2 in 0..64 {
+                let touching = (s1 / 8).abs_diff(s2 / 8) <= 1 && (s1 % 8).abs_diff(s2 % 8) <= 1;
+
+                if touching || (off_diagonal(s1) == 0 && off_diagonal(s2) > 0) {
+                    continue;
+                }
+
+                if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                    both_on_diagonal.push((idx, s2));
+                } else {
+                    encoding.map_kk[idx][s2] = code;
+                    code += 1;
+                }
+            }
+        }
+
+        for (idx, s2) in both_on_diagonal {
+            encoding.map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+
+        for n in 1..64 {
+            for k in 0..6.min(n + 1) {
+                let with = if k > 0 {
+                    encoding.binomial[k - 1][n - 1]
+                } else {
+                    0
+                };
+                let without = if k < n {
+                    encoding.binomial[k][n - 1]
+                } else {
+                    0
+                };
+
+                encoding.binomial[k][n] = with + without;
+            }
+        }
+
+        let mut available = 48;
+
+        for lead_pawns in 1..=5 {
+            for file in 0..4 {
+                let mut idx = 0;
+
+                for rank in 1..7 {
+                    let sq = rank * 8 + file;
+
+                    if lead_pawns == 1 {
+                        available -= 2;
+                        encoding.map_pawns[sq] = available + 1;
+                        encoding.map_pawns[sq ^ 7] = available;
+                    }
+
+                    encoding.lead_pawn_idx[lead_pawns][sq] = idx;
+                    idx += encoding.binomial[lead_pawns - 1][encoding.map_pawns[sq]];
+                }
+
+                encoding.lead_pawns_size[lead_pawns][file] = idx;
+            }
+        }
+
+        encoding
+    }
+}
+
+/// A compressed table for one side to move and leading pawn file.
+#[derive(Clone, Default, Debug)]
+struct Pairs {
+    flags: u8,
+    /// Piece codes in the order they are encoded.
+    pieces: [u8; MAX_PIECES],
+    /// Sizes of the groups of pieces encoded together, zero terminated.
+    group_len: [usize; MAX_PIECES + 1],
+    /// Index multiplier of each group, the last one being the table size.
+    group_idx: [u64; MAX_PIECES + 1],
+    block_size: usize,
+    span: u64,
+    sparse_index_size: usize,
+    num_blocks: usize,
+    block_length_size: usize,
+    min_sym_len: u8,
+    lowest_sym: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    btree: usize,
+    sparse_index: usize,
+    block_lengths: usize,
+    data: usize,
+    map_idx: [u16; 4],
+}
+
+impl Pairs {
+    fn size(&self) -> u64 {
+        let groups = self.group_len.iter().position(|&len| len == 0).unwrap();
+
+        self.group_idx[groups]
+    }
+}
+
+/// A WDL or DTZ file read into memory. Offsets in `Pairs` point into
+/// `data`.
+struct Table {
+    data: Vec<u8>,
+    kind: Kind,
+    piece_count: usize,
+    /// Both sides have the same pieces and only white to move is stored.
+    symmetric: bool,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    /// Pawns of the leading color, then of the other.
+    pawn_count: [usize; 2],
+    /// One compressed table per leading pawn file and side to move.
+    files: Vec<[Pairs; 2]>,
+    dtz_map: usize,
+}
+
+impl Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("kind", &self.kind)
+            .field("bytes", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Table {
+    /// The layout of the table for `material`, without its data.
+    fn new(material: &str, kind: Kind) -> Option<Self> {
+        let (white, black) = material.split_once('v')?;
+        let count = |side: &str, c| side.chars().filter(|&x| x == c).count();
+
+        let piece_count = white.len() + black.len();
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+
+        if piece_count > MAX_PIECES {
+            return None;
+        }
+
+        // the color with fewer pawns leads, for better compression
+        let pawn_count = if black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns) {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+
+        let files = if pawn_count[0] > 0 { 4 } else { 1 };
+
+        Some(Self {
+            data: Vec::new(),
+            kind,
+            piece_count,
+            symmetric: white == black,
+            has_pawns: pawn_count[0] > 0,
+            has_unique_pieces: [white, black]
+                .iter()
+                .any(|side| "QRBNP".chars().any(|c| count(side, c) == 1)),
+            pawn_count,
+            files: vec![Default::default(); files],
+            dtz_map: 0,
+        })
+    }
+
+    fn load(path: &Path, material: &str, kind: Kind) -> Option<Self> {
+        let mut table = Self::new(material, kind)?;
+        table.data = fs::read(path).ok()?;
+
+        if table.data.len() % 64 != 16 {
+            return None;
+        }
+
+        table.parse()?;
+
+        Some(table)
+    }
+
+    /// Number of sides to move stored: DTZ tables and symmetric WDL tables
+    /// only store one.
+    fn sides(&self) -> usize {
+        if self.kind == Kind::Wdl && !self.symmetric {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn pairs(&self, side: usize, file: usize) -> &Pairs {
+        &self.files[file][side % self.sides()]
+    }
+
+    fn parse(&mut self) -> Option<()> {
+        let flags = *self.data.get(4)?;
+
+        if (flags & FILE_HAS_PAWNS != 0) != self.has_pawns
+            || (flags & FILE_SPLIT != 0) == self.symmetric
+        {
+            return None;
+        }
+
+        let sides = self.sides();
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut offset = 5;
+
+        for file in 0..self.files.len() {
+            let order = *self.data.get(offset)?;
+            let pawn_order = if both_pawns {
+                *self.data.get(offset + 1)?
+            } else {
+                0xFF
+            };
+            offset += 1 + both_pawns as usize;
+
+            for k in 0..self.piece_count {
+                let byte = *self.data.get(offset + k)?;
+
+                self.files[file][0].pieces[k] = byte & 0xF;
+                self.files[file][1].pieces[k] = byte >> 4;
+            }
+
+            offset += self.piece_count;
+
+            for side in 0..sides {
+                let shift = side * 4;
+                let order = [(order >> shift) & 0xF, (pawn_order >> shift) & 0xF];
+                let mut pairs = self.files[file][side].clone();
+
+                self.set_groups(&mut pairs, order, file);
+                self.files[file][side] = pairs;
+            }
+        }
+
+        offset += offset & 1;
+
+        for file in 0..self.files.len() {
+            for side in 0..sides {
+                offset = self.set_sizes(file, side, offset)?;
+            }
+        }
+
+        if self.kind == Kind::Dtz {
+            offset = self.set_dtz_map(offset)?;
+        }
+
+        for file in 0..self.files.len() {
+            for side in 0..sides {
+                let pairs = &mut self.files[file][side];
+                pairs.sparse_index = offset;
+                offset += pairs.sparse_index_size * 6;
+            }
+        }
+
+        for file in 0..self.files.len() {
+            for side in 0..sides {
+                let pairs = &mut self.files[file][side];
+                pairs.block_lengths = offset;
+                offset += pairs.block_length_size * 2;
+            }
+        }
+
+        for file in 0..self.files.len() {
+            for side in 0..sides {
+                let pairs = &mut self.files[file][side];
+                offset = offset.next_multiple_of(64);
+                pairs.data = offset;
+                offset += pairs.num_blocks * pairs.block_size;
+            }
+        }
+
+        (offset <= self.data.len()).then_some(())
+    }
+
+    /// Splits the pieces into the groups encoded together and computes the
+    /// index multiplier of each group. `order` holds the positions of the
+    /// leading group and of the remaining pawns in the encoding.
+    fn set_groups(&self, pairs: &mut Pairs, order: [u8; 2], file: usize) {
+        let encoding = &*ENCODING;
+        let mut first_len: i32 = if self.has_pawns {
+            0
+        } else if self.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+
+        let mut n = 0;
+        pairs.group_len[0] = 1;
+
+        for i in 1..self.piece_count {
+            first_len -= 1;
+
+            if first_len > 0 || pairs.pieces[i] == pairs.pieces[i - 1] {
+                pairs.group_len[n] += 1;
+            } else {
+                n += 1;
+                pairs.group_len[n] = 1;
+            }
+        }
+
+        n += 1;
+        pairs.group_len[n] = 0;
+
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free = 64 - pairs.group_len[0] - if both_pawns { pairs.group_len[1] } else { 0 };
+        let mut idx = 1;
+        let mut k = 0;
+
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                pairs.group_idx[0] = idx;
+                idx *= if self.has_pawns {
+                    encoding.lead_pawns_size[pairs.group_len[0]][file]
+                } else if self.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                pairs.group_idx[1] = idx;
+                idx *= encoding.binomial[pairs.group_len[1]][48 - pairs.group_len[0]];
+            } else {
+                pairs.group_idx[next] = idx;
+                idx *= encoding.binomial[pairs.group_len[next]][free];
+                free -= pairs.group_len[next];
+                next += 1;
+            }
+
+            k += 1;
+        }
+
+        pairs.group_idx[n] = idx;
+    }
+
+    /// Reads the block layout and the canonical Huffman code of a compressed
+    /// table, returning the offset past them.
+    fn set_sizes(&mut self, file: usize, side: usize, offset: usize) -> Option<usize> {
+        let data = &self.data;
+        let pairs = &mut self.files[file][side];
+        let mut offset = offset;
+
+        pairs.flags = *data.get(offset)?;
+        offset += 1;
+
+        if pairs.flags & FLAG_SINGLE_VALUE != 0 {
+            pairs.min_sym_len = *data.get(offset)?;
+            return Some(offset + 1);
+        }
+
+        pairs.block_size = 1 << *data.get(offset)?;
+        pairs.span = 1 << *data.get(offset + 1)?;
+        pairs.sparse_index_size = pairs.size().div_ceil(pairs.span) as usize;
+        let padding = *data.get(offset + 2)? as usize;
+        pairs.num_blocks = u32_le(data, offset + 3)? as usize;
+        pairs.block_length_size = pairs.num_blocks + padding;
+        let max_sym_len = *data.get(offset + 7)?;
+        pairs.min_sym_len = *data.get(offset + 8)?;
+        offset += 9;
+
+        if pairs.min_sym_len == 0 || max_sym_len < pairs.min_sym_len || max_sym_len > 32 {
+            return None;
+        }
+
+        // longer codes have lower values: base64[i] is the lowest code of
+        // length min_sym_len + i, left-aligned in 64 bits
+        pairs.lowest_sym = offset;
+        let lengths = (max_sym_len - pairs.min_sym_len) as usize + 1;
+        pairs.base64 = vec![0; lengths];
+
+        for i in (0..lengths - 1).rev() {
+            let lowest = u16_le(data, offset + 2 * i)? as u64;
+            let next = u16_le(data, offset + 2 * i + 2)? as u64;
+
+            pairs.base64[i] = (pairs.base64[i + 1] + lowest).checked_sub(next)? / 2;
+        }
+
+        for (i, base) in pairs.base64.iter_mut().enumerate() {
+            *base <<= 64 - i - pairs.min_sym_len as usize;
+        }
+
+        offset += 2 * lengths;
+        let symbols = u16_le(data, offset)? as usize;
+        offset += 2;
+        pairs.btree = offset;
+
+        // every symbol expands into a pair of symbols or is a leaf value
+        pairs.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+
+        for sym in 0..symbols {
+            if !visited[sym] {
+                pairs.symlen[sym] = set_symlen(data, pairs, sym, &mut visited)?;
+            }
+        }
+
+        Some(offset + 3 * symbols + (symbols & 1))
+    }
+
+    /// Reads the maps from stored to actual DTZ values of each file.
+    fn set_dtz_map(&mut self, offset: usize) -> Option<usize> {
+        let mut offset = offset;
+        self.dtz_map = offset;
+
+        for file in 0..self.files.len() {
+            let flags = self.files[file][0].flags;
+
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+
+            if flags & FLAG_WIDE != 0 {
+                offset += offset & 1;
+
+                for i in 0..4 {
+                    self.files[file][0].map_idx[i] = ((offset - self.dtz_map) / 2 + 1) as u16;
+                    offset += 2 * u16_le(&self.data, offset)? as usize + 2;
+                }
+            } else {
+                for i in 0..4 {
+                    self.files[file][0].map_idx[i] = (offset - self.dtz_map + 1) as u16;
+                    offset += *self.data.get(offset)? as usize + 1;
+                }
+            }
+        }
+
+        Some(offset + (offset & 1))
+    }
+
+    /// The side to move and leading pawn file of the compressed table
+    /// holding `pos`, and its index there. With `flip` set the colors are
+    /// swapped to match the table.
+    fn encode(&self, pos: &Position, flip: bool) -> Option<(usize, usize, u64)> {
+        let encoding = &*ENCODING;
+        let black_to_move = pos.side_to_move() == Color::Black;
+
+        // symmetric tables only store white to move
+        let flip = flip || (self.symmetric && black_to_move);
+        let (flip_color, flip_square) = if flip { (8, 56) } else { (0, 0) };
+        let side = (flip ^ black_to_move) as usize;
+
+        let mut squares = [0usize; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut file = 0;
+
+        if self.has_pawns {
+            let code = self.files[0][0].pieces[0] ^ flip_color;
+            let color = if code & 8 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            for square in pos.bitboards().piece(color, Piece::Pawn).squares() {
+                squares[size] = square.index() ^ flip_square;
+                pieces[size] = self.files[0][0].pieces[0];
+                size += 1;
+            }
+
+            lead_pawns = size;
+
+            // the leading pawn is the one closest to the edge, then to the
+            // first rank
+            let lead = (0..lead_pawns).max_by_key(|&i| encoding.map_pawns[squares[i]])?;
+            squares.swap(0, lead);
+
+            file = (squares[0] % 8).min(7 - squares[0] % 8);
+        }
+
+        for square in pos.bitboards().all().squares() {
+            if self.has_pawns && squares[..lead_pawns].contains(&(square.index() ^ flip_square)) {
+                continue;
+            }
+
+            let (color, piece) = pos.piece_at(square)?;
+
+            if size == MAX_PIECES {
+                return None;
+            }
+
+            squares[size] = square.index() ^ flip_square;
+            pieces[size] = piece_code(color, piece) ^ flip_color;
+            size += 1;
+        }
+
+        if size != self.piece_count {
+            return None;
+        }
+
+        let pairs = self.pairs(side, file);
+
+        // order the pieces as the table encodes them
+        for i in lead_pawns..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|&j| pairs.pieces[i] == pieces[j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+
+        // the leading piece goes to the queen side
+        if squares[0] % 8 > 3 {
+            squares[..size].iter_mut().for_each(|sq| *sq ^= 7);
+        }
+
+        let mut index;
+
+        if self.has_pawns {
+            index = encoding.lead_pawn_idx[lead_pawns][squares[0]];
+
+            squares[1..lead_pawns].sort_by_key(|&sq| encoding.map_pawns[sq]);
+
+            for (i, &sq) in squares[1..lead_pawns].iter().enumerate() {
+                index += encoding.binomial[i + 1][encoding.map_pawns[sq]];
+            }
+        } else {
+            // below the fifth rank, then below the a1-h8 diagonal
+            if squares[0] / 8 > 3 {
+                squares[..size].iter_mut().for_each(|sq| *sq ^= 56);
+            }
+
+            for i in 0..pairs.group_len[0] {
+                match off_diagonal(squares[i]) {
+                    0 => continue,
+                    off if off > 0 => squares[i..size]
+                        .iter_mut()
+                        .for_each(|sq| *sq = ((*sq >> 3) | (*sq << 3)) & 63),
+                    _ => {}
+                }
+
+                break;
+            }
+
+            index = if self.has_unique_pieces {
+                self.encode_unique(&squares)
+            } else {
+                encoding.map_kk[encoding.map_a1d1d4[squares[0]] as usize][squares[1]]
+            };
+        }
+
+        index *= pairs.group_idx[0];
+
+        let mut start = pairs.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+
+        for group in 1..=MAX_PIECES {
+            let len = pairs.group_len[group];
+
+            if len == 0 {
+                break;
+            }
+
+            squares[start..start + len].sort_unstable();
+
+            let mut n = 0;
+
+            for i in 0..len {
+                let sq = squares[start + i];
+                let adjust = squares[..start].iter().filter(|&&other| sq > other).count();
+                let free = sq.checked_sub(adjust + 8 * remaining_pawns as usize)?;
+
+                n += encoding.binomial[i + 1][free];
+            }
+
+            remaining_pawns = false;
+            index += n * pairs.group_idx[group];
+            start += len;
+        }
+
+        Some((side, file, index))
+    }
+
+    /// Encodes the first three pieces of a pawnless table with unique
+    /// pieces, the first one being in the a1-d1-d4 triangle.
+    fn encode_unique(&self, squares: &[usize]) -> u64 {
+        let encoding = &*ENCODING;
+        let [s0, s1, s2] = [squares[0], squares[1], squares[2]];
+        let adjust1 = (s1 > s0) as u64;
+        let adjust2 = (s2 > s0) as u64 + (s2 > s1) as u64;
+        let rank = |sq: usize| (sq / 8) as u64;
+
+        if off_diagonal(s0) != 0 {
+            (encoding.map_a1d1d4[s0] * 63 + (s1 as u64 - adjust1)) * 62 + s2 as u64 - adjust2
+        } else if off_diagonal(s1) != 0 {
+            (6 * 63 + rank(s0) * 28 + encoding.map_b1h1h7[s1]) * 62 + s2 as u64 - adjust2
+        } else if off_diagonal(s2) != 0 {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + rank(s0) * 7 * 28
+                + (rank(s1) - adjust1) * 28
+                + encoding.map_b1h1h7[s2]
+        } else {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + 4 * 7 * 28
+                + rank(s0) * 7 * 6
+                + (rank(s1) - adjust1) * 6
+                + (rank(s2) - adjust2)
+        }
+    }
+
+    /// Decodes the value at `index`: the block holding it is found through
+    /// the sparse index, then its Huffman coded symbols are skipped until
+    /// the one covering `index`, which expands by recursive pairing.
+    fn decompress(&self, pairs: &Pairs, index: u64) -> Option<u16> {
+        if pairs.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(pairs.min_sym_len as u16);
+        }
+
+        let data = &self.data;
+        let entry = pairs.sparse_index + (index / pairs.span) as usize * 6;
+        let mut block = u32_le(data, entry)? as usize;
+        let mut offset =
+            u16_le(data, entry + 4)? as i64 + (index % pairs.span) as i64 - (pairs.span / 2) as i64;
+
+        let block_length = |block: usize| -> Option<i64> {
+            (block < pairs.block_length_size)
+                .then(|| u16_le(data, pairs.block_lengths + 2 * block))?
+                .map(|length| length as i64 + 1)
+        };
+
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)?;
+        }
+
+        while offset >= block_length(block)? {
+            offset -= block_length(block)?;
+            block += 1;
+        }
+
+        let mut ptr = pairs.data + block * pairs.block_size;
+        let mut buf = u64::from_be_bytes(read(data, ptr)?);
+        let mut buf_size = 64;
+        ptr += 8;
+
+        let min_sym_len = pairs.min_sym_len as usize;
+        let mut sym;
+
+        loop {
+            let mut len = 0;
+
+            while buf < pairs.base64[len] {
+                len += 1;
+
+                if len == pairs.base64.len() {
+                    return None;
+                }
+            }
+
+            sym = ((buf - pairs.base64[len]) >> (64 - len - min_sym_len)) as usize;
+            sym += u16_le(data, pairs.lowest_sym + 2 * len)? as usize;
+
+            let expanded = *pairs.symlen.get(sym)? as i64 + 1;
+
+            if offset < expanded {
+                break;
+            }
+
+            offset -= expanded;
+
+            let len = len + min_sym_len;
+            buf <<= len;
+            buf_size -= len;
+
+            if buf_size <= 32 {
+                // the last block may end right before the file does
+                let word = read(data, ptr).map_or(0, u32::from_be_bytes);
+
+                buf_size += 32;
+                buf |= (word as u64) << (64 - buf_size);
+                ptr += 4;
+            }
+        }
+
+        while pairs.symlen[sym] != 0 {
+            let (left, right) = children(data, pairs, sym)?;
+            let left_len = *pairs.symlen.get(left)? as i64 + 1;
+
+            if offset < left_len {
+                sym = left;
+            } else {
+                offset -= left_len;
+                sym = right;
+            }
+        }
+
+        Some(children(data, pairs, sym)?.0 as u16)
+    }
+
+    /// Converts a stored DTZ value to plies, given the WDL of the position.
+    fn map_dtz(&self, file: usize, value: u16, wdl: i32) -> Option<i32> {
+        let pairs = &self.files[file][0];
+        let flags = pairs.flags;
+        let mut value = value as i32;
+
+        if flags & FLAG_MAPPED != 0 {
+            let map = [1, 3, 0, 2, 0][(wdl + 2) as usize];
+            let index = pairs.map_idx[map] as usize + value as usize;
+
+            value = if flags & FLAG_WIDE != 0 {
+                u16_le(&self.data, self.dtz_map + 2 * index)? as i32
+            } else {
+                *self.data.get(self.dtz_map + index)? as i32
+            };
+        }
+
+        // values are stored in moves unless the flags say otherwise
+        let in_moves = match wdl {
+            2 => flags & FLAG_WIN_PLIES == 0,
+            -2 => flags & FLAG_LOSS_PLIES == 0,
+            _ => true,
+        };
+
+        if in_moves {
+            value *= 2;
+        }
+
+        Some(value + 1)
+    }
+}
+
+/// Number of symbols, minus one, that `sym` expands into.
+fn set_symlen(data: &[u8], pairs: &mut Pairs, sym: usize, visited: &mut [bool]) -> Option<u8> {
+    visited[sym] = true;
+
+    let (left, right) = children(data, pairs, sym)?;
+
+    if right == LEAF as usize {
+        return Some(0);
+    }
+
+    for child in [left, right] {
+        if !*visited.get(child)? {
+            pairs.symlen[child] = set_symlen(data, pairs, child, visited)?;
+        }
+    }
+
+    Some(
+        pairs.symlen[left]
+            .wrapping_add(pairs.symlen[right])
+            .wrapping_add(1),
+    )
+}
+
+/// The pair of symbols `sym` expands into, or its value and `LEAF`.
+fn children(data: &[u8], pairs: &Pairs, sym: usize) -> Option<(usize, usize)> {
+    let [a, b, c] = read(data, pairs.btree + 3 * sym)?;
+
+    Some((
+        ((b as usize & 0xF) << 8) | a as usize,
+        ((c as usize) << 4) | (b as usize >> 4),
+    ))
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    data.get(offset..offset + N)?.try_into().ok()
+}
+
+fn u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    read(data, offset).map(u16::from_le_bytes)
+}
+
+fn u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    read(data, offset).map(u32::from_le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use psce_core::Square;
+    use psce_tablebase::{Outcome, Tablebases};
+
+    use super::*;
+
+    const TABLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/syzygy");
+
+    /// Rewrites the tables in `assets/syzygy` from the generated endgames,
+    /// run with `cargo test --release -p psce_search -- --ignored`.
+    #[test]
+    #[ignore]
+    fn write_test_tables() {
+        let tables = [
+            ("KQvK", Kind::Dtz),
+            ("KQvK", Kind::Wdl),
+            ("KRvK", Kind::Dtz),
+            ("KRvK", Kind::Wdl),
+            ("KBvK", Kind::Wdl),
+            ("KNvK", Kind::Wdl),
+            ("KPvK", Kind::Wdl),
+            ("KRvKB", Kind::Wdl),
+        ];
+
+        let mut tablebases = Tablebases::new();
+
+        for (material, kind) in tables {
+            tablebases.generate(material).unwrap();
+
+            let bytes = writer::write(material, kind, |pos| {
+                let outcome = tablebases.probe(pos).unwrap();
+
+                // without captures or pawn moves for the winning side, as
+                // in KQvK and KRvK, the distance to zeroing is the
+                // distance to mate
+                match (kind, outcome) {
+                    (Kind::Wdl, Outcome::Win(_)) => Some(4),
+                    (Kind::Wdl, Outcome::Draw) => Some(2),
+                    (Kind::Wdl, Outcome::Loss(_)) => Some(0),
+                    (Kind::Dtz, Outcome::Win(plies) | Outcome::Loss(plies)) => {
+                        Some(plies.saturating_sub(1) as u16)
+                    }
+                    (Kind::Dtz, Outcome::Draw) => None,
+                }
+            });
+
+            let extension = match kind {
+                Kind::Wdl => "rtbw",
+                Kind::Dtz => "rtbz",
+            };
+
+            fs::write(format!("{}/{}.{}", TABLES, material, extension), bytes).unwrap();
+        }
+    }
+
+    fn probe_wdl(syzygy: &Syzygy, fen: &str) -> Option<Wdl> {
+        syzygy.probe_wdl(&Position::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_open_discovers_tables() {
+        let dir = std::env::temp_dir().join(format!("psce-syzygy-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("KRvKB.rtbw"), WDL_MAGIC).unwrap();
+        fs::write(dir.join("KRvKB.rtbz"), DTZ_MAGIC).unwrap();
+        fs::write(dir.join("KQvK.rtbw"), [0; 4]).unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let syzygy = Syzygy::open(dir.to_str().unwrap()).unwrap();
+
+        assert_eq!(syzygy.tables(), (1, 1));
+        assert_eq!(syzygy.max_pieces(), 4);
+
+        // the truncated table is rejected when probed
+        let pos = Position::from_fen("8/8/8/8/8/2k5/2b5/R3K3 w - - 0 1").unwrap();
+        assert_eq!(syzygy.probe_wdl(&pos), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_probe_wdl() {
+        let syzygy = Syzygy::open(TABLES).unwrap();
+
+        assert_eq!(syzygy.tables(), (6, 2));
+
+        let cases = [
+            ("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1", Wdl::Win),
+            // stalemate
+            ("7k/5Q2/5K2/8/8/8/8/8 b - - 0 1", Wdl::Draw),
+            // the same endgame with colors swapped
+            ("8/8/8/8/8/6k1/5q2/7K b - - 0 1", Wdl::Win),
+            ("8/8/8/8/8/5k2/6q1/7K w - - 0 1", Wdl::Loss),
+            // black to move captures the rook
+            ("8/8/8/8/8/8/1k6/1R2K3 b - - 0 1", Wdl::Draw),
+            ("8/8/8/8/8/8/1k6/1R2K3 w - - 0 1", Wdl::Win),
+            ("8/8/8/3k4/8/8/8/3BK3 w - - 0 1", Wdl::Draw),
+            ("8/8/8/3k4/8/8/8/3NK3 b - - 0 1", Wdl::Draw),
+            // the rook takes the bishop, or the bishop takes the rook
+            ("4k3/8/8/8/8/8/8/R1b1K3 w - - 0 1", Wdl::Win),
+            ("4k3/8/8/8/8/8/1b6/R3K3 b - - 0 1", Wdl::Draw),
+            ("r3k3/1B6/8/8/8/8/8/4K3 w - - 0 1", Wdl::Draw),
+            ("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", Wdl::Loss),
+            ("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", Wdl::Win),
+            ("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1", Wdl::Loss),
+            // rook pawn with the defending king in the corner
+            ("k7/8/8/8/8/8/P7/K7 w - - 0 1", Wdl::Draw),
+            ("7k/8/8/8/8/8/7P/7K w - - 0 1", Wdl::Draw),
+            ("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1", Wdl::Draw),
+        ];
+
+        for (fen, wdl) in cases {
+            assert_eq!(probe_wdl(&syzygy, fen), Some(wdl), "{}", fen);
+        }
+
+        assert_eq!(probe_wdl(&syzygy, "4k3/8/8/8/8/8/3PP3/4K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn test_probe_matches_generated_tables() {
+        let syzygy = Syzygy::open(TABLES).unwrap();
+        let mut rng = fastrand::Rng::with_seed(7);
+
+        for material in ["KQvK", "KRvK"] {
+            let mut tablebases = Tablebases::new();
+            tablebases.generate(material).unwrap();
+
+            let piece = Piece::from_char(material.chars().nth(1).unwrap()).unwrap();
+            let mut probed = 0;
+
+            while probed < 500 {
+                let strong = if rng.bool() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let squares: Vec<_> = (0..3)
+                    .map(|_| Square::new(rng.u8(0..64)).unwrap())
+                    .collect();
+
+                if squares[1..].contains(&squares[0]) || squares[1] == squares[2] {
+                    continue;
+                }
+
+                let pieces = [
+                    (strong, Piece::King, squares[0]),
+                    (strong, piece, squares[1]),
+                    (!strong, Piece::King, squares[2]),
+                ];
+                let side_to_move = if rng.bool() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let pos = Position::from_pieces(&pieces, side_to_move);
+
+                if MoveGen::is_in_check(&pos, !side_to_move) {
+                    continue;
+                }
+
+                let (wdl, dtz) = match tablebases.probe(&pos).unwrap() {
+                    Outcome::Win(plies) => (Wdl::Win, plies as i32),
+                    Outcome::Draw => (Wdl::Draw, 0),
+                    // a mated side is one ply from its opponent's win
+                    Outcome::Loss(plies) => (Wdl::Loss, -(plies.max(1) as i32)),
+                };
+
+                assert_eq!(syzygy.probe_wdl(&pos), Some(wdl), "{}", pos.to_fen());
+                assert_eq!(syzygy.probe_dtz(&pos), Some(dtz), "{}", pos.to_fen());
+
+                probed += 1;
+            }
+        }
+    }
+}
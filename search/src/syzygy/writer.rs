@@ -0,0 +1,466 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use psce_core::Square;
+
+use super::*;
+
+const BLOCK_SIZE_BITS: u8 = 8;
+const SPAN_BITS: u8 = 10;
+
+/// Pairs seen fewer times than this are not worth a symbol.
+const MIN_PAIR_COUNT: u32 = 8;
+
+/// Writes the table for `material` in the Syzygy format, with `value`
+/// giving the stored value of each position or `None` if any will do.
+/// DTZ tables store white to move.
+pub(super) fn write(
+    material: &str,
+    kind: Kind,
+    value: impl Fn(&Position) -> Option<u16>,
+) -> Vec<u8> {
+    let mut table = Table::new(material, kind).unwrap();
+    let pieces = encoding_order(&table, material);
+    let both_pawns = table.has_pawns && table.pawn_count[1] > 0;
+    let order = [0, if both_pawns { 1 } else { 0xF }];
+
+    for file in 0..table.files.len() {
+        for side in 0..2 {
+            let mut pairs = Pairs::default();
+
+            for (k, &(color, piece)) in pieces.iter().enumerate() {
+                pairs.pieces[k] = piece_code(color, piece);
+            }
+
+            table.set_groups(&mut pairs, order, file);
+            table.files[file][side] = pairs;
+        }
+    }
+
+    let mut values: Vec<Vec<Vec<Option<u16>>>> = table
+        .files
+        .iter()
+        .map(|sides| {
+            sides[..table.sides()]
+                .iter()
+                .map(|pairs| vec![None; pairs.size() as usize])
+                .collect()
+        })
+        .collect();
+
+    let side_to_moves: &[Color] = match kind {
+        Kind::Wdl => &[Color::White, Color::Black],
+        Kind::Dtz => &[Color::White],
+    };
+
+    for_each_placement(&pieces, &mut Vec::new(), &mut |placed| {
+        for &side_to_move in side_to_moves {
+            let pos = Position::from_pieces(placed, side_to_move);
+
+            if MoveGen::is_in_check(&pos, !side_to_move) {
+                continue;
+            }
+
+            let (side, file, index) = table.encode(&pos, false).unwrap();
+            let stored = &mut values[file][side % table.sides()][index as usize];
+            let value = value(&pos);
+
+            assert!(
+                stored.is_none() || value.is_none() || *stored == value,
+                "{} has two values",
+                pos.to_fen()
+            );
+
+            *stored = stored.or(value);
+        }
+    });
+
+    let flags = match kind {
+        Kind::Wdl => 0,
+        Kind::Dtz => FLAG_WIN_PLIES | FLAG_LOSS_PLIES,
+    };
+
+    let compressed: Vec<Vec<Compressed>> = values
+        .iter()
+        .map(|sides| sides.iter().map(|values| compress(values, flags)).collect())
+        .collect();
+
+    let mut out = match kind {
+        Kind::Wdl => WDL_MAGIC.to_vec(),
+        Kind::Dtz => DTZ_MAGIC.to_vec(),
+    };
+
+    out.push(
+        if table.symmetric { 0 } else { FILE_SPLIT }
+            | if table.has_pawns { FILE_HAS_PAWNS } else { 0 },
+    );
+
+    for _ in 0..table.files.len() {
+        out.push(order[0] | order[0] << 4);
+
+        if both_pawns {
+            out.push(order[1] | order[1] << 4);
+        }
+
+        for &(color, piece) in &pieces {
+            let code = piece_code(color, piece);
+            out.push(code | code << 4);
+        }
+    }
+
+    out.resize(out.len().next_multiple_of(2), 0);
+
+    let sections = compressed.iter().flatten();
+
+    for section in sections.clone() {
+        out.extend(&section.sizes);
+    }
+
+    if kind == Kind::Dtz {
+        out.resize(out.len().next_multiple_of(2), 0);
+    }
+
+    for section in sections.clone() {
+        out.extend(&section.sparse_index);
+    }
+
+    for section in sections.clone() {
+        out.extend(&section.block_lengths);
+    }
+
+    for section in sections {
+        out.resize(out.len().next_multiple_of(64), 0);
+        out.extend(&section.blocks);
+    }
+
+    out.resize(out.len().next_multiple_of(64) + 16, 0);
+    out
+}
+
+/// The pieces of `material` in the order they are encoded: the leading
+/// pawns, the other pawns and the kings first, unique pieces before the
+/// others.
+fn encoding_order(table: &Table, material: &str) -> Vec<(Color, Piece)> {
+    let (white, black) = material.split_once('v').unwrap();
+    let mut pieces = Vec::new();
+
+    for (color, side) in [(Color::White, white), (Color::Black, black)] {
+        for c in side.chars() {
+            let count = side.chars().filter(|&x| x == c).count();
+            pieces.push((color, Piece::from_char(c).unwrap(), count));
+        }
+    }
+
+    let white_pawns = white.chars().filter(|&c| c == 'P').count();
+    let leading = if table.pawn_count[0] == white_pawns {
+        Color::White
+    } else {
+        Color::Black
+    };
+
+    pieces.sort_by_key(|&(color, piece, count)| match piece {
+        Piece::Pawn => (0, color != leading, 0),
+        Piece::King => (1, color == Color::Black, 0),
+        _ => (
+            2 + (count > 1) as usize,
+            color == Color::Black,
+            piece as usize,
+        ),
+    });
+
+    pieces
+        .into_iter()
+        .map(|(color, piece, _)| (color, piece))
+        .collect()
+}
+
+fn for_each_placement(
+    pieces: &[(Color, Piece)],
+    placed: &mut Vec<(Color, Piece, Square)>,
+    f: &mut impl FnMut(&[(Color, Piece, Square)]),
+) {
+    let Some(&(color, piece)) = pieces.get(placed.len()) else {
+        f(placed);
+        return;
+    };
+
+    for square in Square::all() {
+        let pawn_rank = matches!(square.index() / 8, 1..=6);
+
+        if placed.iter().any(|&(_, _, sq)| sq == square) || (piece == Piece::Pawn && !pawn_rank) {
+            continue;
+        }
+
+        placed.push((color, piece, square));
+        for_each_placement(pieces, placed, f);
+        placed.pop();
+    }
+}
+
+/// One compressed table, split in the sections of the file.
+struct Compressed {
+    sizes: Vec<u8>,
+    sparse_index: Vec<u8>,
+    block_lengths: Vec<u8>,
+    blocks: Vec<u8>,
+}
+
+/// Compresses `values` by recursive pairing of the most frequent adjacent
+/// symbols, then canonical Huffman coding of the symbols.
+fn compress(values: &[Option<u16>], flags: u8) -> Compressed {
+    // values that do not matter repeat the previous one
+    let mut last = values.iter().flatten().next().copied().unwrap_or(0);
+    let mut values: Vec<u16> = values
+        .iter()
+        .map(|value| {
+            last = value.unwrap_or(last);
+            last
+        })
+        .collect();
+
+    if values.iter().all(|&value| value == values[0]) {
+        return Compressed {
+            sizes: vec![flags | FLAG_SINGLE_VALUE, values[0] as u8],
+            sparse_index: Vec::new(),
+            block_lengths: Vec::new(),
+            blocks: Vec::new(),
+        };
+    }
+
+    // every sparse index entry points into the blocks
+    let span = 1usize << SPAN_BITS;
+    let entries = values.len().div_ceil(span);
+    values.resize(values.len().max((entries - 1) * span + span / 2 + 1), last);
+
+    let mut leaves: Vec<u16> = values.clone();
+    leaves.sort_unstable();
+    leaves.dedup();
+
+    // symbols are leaves (value, LEAF) or pairs of symbols
+    let mut tree: Vec<(u16, u16)> = leaves.iter().map(|&value| (value, LEAF)).collect();
+    let mut expanded: Vec<usize> = vec![1; tree.len()];
+    let mut stream: Vec<u16> = values
+        .iter()
+        .map(|value| leaves.binary_search(value).unwrap() as u16)
+        .collect();
+
+    while tree.len() < LEAF as usize {
+        let mut counts: HashMap<(u16, u16), u32> = HashMap::new();
+        let mut previous = None;
+
+        for (i, pair) in stream.windows(2).enumerate() {
+            let pair = (pair[0], pair[1]);
+
+            // runs like `aaa` hold a single pair
+            if previous == Some((pair, i - 1)) {
+                continue;
+            }
+
+            *counts.entry(pair).or_default() += 1;
+            previous = Some((pair, i));
+        }
+
+        let Some((pair, count)) = counts
+            .into_iter()
+            .filter(|&((a, b), _)| expanded[a as usize] + expanded[b as usize] <= 256)
+            .max_by_key(|&(pair, count)| (count, Reverse(pair)))
+        else {
+            break;
+        };
+
+        if count < MIN_PAIR_COUNT {
+            break;
+        }
+
+        let sym = tree.len() as u16;
+        tree.push(pair);
+        expanded.push(expanded[pair.0 as usize] + expanded[pair.1 as usize]);
+
+        let mut paired = Vec::with_capacity(stream.len());
+        let mut i = 0;
+
+        while i < stream.len() {
+            if i + 1 < stream.len() && (stream[i], stream[i + 1]) == pair {
+                paired.push(sym);
+                i += 2;
+            } else {
+                paired.push(stream[i]);
+                i += 1;
+            }
+        }
+
+        stream = paired;
+    }
+
+    let mut frequencies = vec![0u64; tree.len()];
+
+    for &sym in &stream {
+        frequencies[sym as usize] += 1;
+    }
+
+    let lengths = huffman_lengths(&frequencies);
+    let min_len = *lengths.iter().filter(|&&len| len > 0).min().unwrap();
+    let max_len = *lengths.iter().max().unwrap();
+
+    assert!(max_len <= 32, "Huffman code too long");
+
+    // the symbols of each code length get consecutive ids, longest codes
+    // first, then the symbols that only appear in pairs
+    let mut ids: Vec<usize> = (0..tree.len()).collect();
+    ids.sort_by_key(|&sym| (lengths[sym] == 0, Reverse(lengths[sym])));
+
+    let mut new_id = vec![0; tree.len()];
+
+    for (id, &sym) in ids.iter().enumerate() {
+        new_id[sym] = id;
+    }
+
+    let mut counts = vec![0u64; max_len + 2];
+
+    for &len in &lengths {
+        counts[len] += 1;
+    }
+
+    // canonical codes: the lowest code of each length follows from the
+    // next longer one
+    let mut lowest = vec![0u64; max_len + 2];
+    let mut base = vec![0u64; max_len + 2];
+
+    for len in (min_len..max_len).rev() {
+        lowest[len] = lowest[len + 1] + counts[len + 1];
+        assert!((base[len + 1] + counts[len + 1]).is_multiple_of(2));
+        base[len] = (base[len + 1] + counts[len + 1]) / 2;
+    }
+
+    let code = |sym: usize| base[lengths[sym]] + new_id[sym] as u64 - lowest[lengths[sym]];
+
+    // pack the symbols in blocks, a symbol never crossing a block
+    let block_size = 1usize << BLOCK_SIZE_BITS;
+    let mut blocks: Vec<Vec<u8>> = Vec::new();
+    let mut block_values: Vec<usize> = Vec::new();
+    let mut bits = 0;
+
+    for &sym in &stream {
+        let sym = sym as usize;
+        let len = lengths[sym];
+
+        if blocks.is_empty()
+            || bits + len > block_size * 8
+            || block_values.last().unwrap() + expanded[sym] > 65536
+        {
+            blocks.push(vec![0; block_size]);
+            block_values.push(0);
+            bits = 0;
+        }
+
+        let block = blocks.last_mut().unwrap();
+
+        for bit in 0..len {
+            if code(sym) >> (len - 1 - bit) & 1 != 0 {
+                block[(bits + bit) / 8] |= 0x80 >> ((bits + bit) % 8);
+            }
+        }
+
+        bits += len;
+        *block_values.last_mut().unwrap() += expanded[sym];
+    }
+
+    let mut starts = vec![0];
+
+    for &count in &block_values {
+        starts.push(starts.last().unwrap() + count);
+    }
+
+    let mut sparse_index = Vec::new();
+
+    for k in 0..entries {
+        let value = k * span + span / 2;
+        let block = starts.partition_point(|&start| start <= value) - 1;
+
+        sparse_index.extend((block as u32).to_le_bytes());
+        sparse_index.extend(((value - starts[block]) as u16).to_le_bytes());
+    }
+
+    let mut sizes = vec![flags, BLOCK_SIZE_BITS, SPAN_BITS, 0];
+    sizes.extend((blocks.len() as u32).to_le_bytes());
+    sizes.extend([max_len as u8, min_len as u8]);
+
+    for &lowest in &lowest[min_len..=max_len] {
+        sizes.extend((lowest as u16).to_le_bytes());
+    }
+
+    sizes.extend((tree.len() as u16).to_le_bytes());
+
+    for &sym in &ids {
+        let (left, right) = match tree[sym] {
+            (value, LEAF) => (value, LEAF),
+            (left, right) => (new_id[left as usize] as u16, new_id[right as usize] as u16),
+        };
+
+        sizes.extend([
+            left as u8,
+            (left >> 8) as u8 | (right << 4) as u8,
+            (right >> 4) as u8,
+        ]);
+    }
+
+    if tree.len() % 2 == 1 {
+        sizes.push(0);
+    }
+
+    Compressed {
+        sizes,
+        sparse_index,
+        block_lengths: block_values
+            .iter()
+            .flat_map(|&count| (count as u16 - 1).to_le_bytes())
+            .collect(),
+        blocks: blocks.concat(),
+    }
+}
+
+/// Huffman code length of each symbol, zero for those that never appear.
+fn huffman_lengths(frequencies: &[u64]) -> Vec<usize> {
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = frequencies
+        .iter()
+        .enumerate()
+        .filter(|&(_, &frequency)| frequency > 0)
+        .map(|(sym, &frequency)| Reverse((frequency, sym)))
+        .collect();
+
+    let mut parent: Vec<usize> = vec![usize::MAX; frequencies.len()];
+
+    if heap.len() == 1 {
+        let mut lengths = vec![0; frequencies.len()];
+        lengths[heap.pop().unwrap().0.1] = 1;
+        return lengths;
+    }
+
+    while heap.len() > 1 {
+        let Reverse((a, left)) = heap.pop().unwrap();
+        let Reverse((b, right)) = heap.pop().unwrap();
+
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[left] = node;
+        parent[right] = node;
+
+        heap.push(Reverse((a + b, node)));
+    }
+
+    (0..frequencies.len())
+        .map(|sym| {
+            let mut len = 0;
+            let mut node = sym;
+
+            while parent[node] != usize::MAX {
+                node = parent[node];
+                len += 1;
+            }
+
+            len
+        })
+        .collect()
+}
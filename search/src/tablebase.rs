@@ -0,0 +1,195 @@
+use std::{fmt::Debug, ops::Neg};
+
+use psce_core::{Castling, Move, Piece, Position, Variant};
+use psce_movegen::MoveGen;
+
+use crate::CHECKMATE_SCORE;
+
+/// Score of a tablebase win, below every mate score so that real mates
+/// found by the search are still preferred.
+pub const TB_WIN_SCORE: i32 = CHECKMATE_SCORE - 2_000;
+
+/// Win/draw/loss from the point of view of the side to move. Cursed wins
+/// and blessed losses are wins and losses that the 50-move rule turns into
+/// draws.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Self::Loss => Self::Win,
+            Self::BlessedLoss => Self::CursedWin,
+            Self::Draw => Self::Draw,
+            Self::CursedWin => Self::BlessedLoss,
+            Self::Win => Self::Loss,
+        }
+    }
+}
+
+/// Endgame tablebase probed by the search. Positions with castling rights
+/// are never probed.
+pub trait Tablebase: Debug + Send + Sync {
+    /// Largest number of pieces, kings included, covered by the tables.
+    fn max_pieces(&self) -> usize;
+
+    fn probe_wdl(&self, pos: &Position) -> Option<Wdl>;
+
+    /// Distance in plies to the next capture or pawn move that keeps the
+    /// result, positive when the side to move wins and negative when it
+    /// loses. `None` if the tables do not store it.
+    fn probe_dtz(&self, pos: &Position) -> Option<i32>;
+
+    /// Distance to mate in plies with the same signs as
+    /// [`probe_dtz`](Self::probe_dtz), ignoring the 50-move rule. `None` if
+    /// the tables do not store it.
+    fn probe_dtm(&self, _pos: &Position) -> Option<i32> {
+        None
+    }
+}
+
+pub fn can_probe(tablebase: &dyn Tablebase, pos: &Position) -> bool {
+    pos.variant() == Variant::Standard
+        && pos.castling() == Castling::NONE
+        && pos.bitboards().all().count() as usize <= tablebase.max_pieces()
+}
+
+pub fn score(wdl: Wdl, ply: usize) -> i32 {
+    match wdl {
+        Wdl::Win => TB_WIN_SCORE - ply as i32,
+        Wdl::Loss => -TB_WIN_SCORE + ply as i32,
+        _ => 0,
+    }
+}
+
+/// Returns the root moves that preserve the tablebase result, or `None` if
+/// the position is not covered. Winning moves with the shortest DTM are
+/// preferred when the tables store it, otherwise those that zero the 50-move
+/// counter or have the shortest DTZ. Losing moves with the longest distance
+/// are preferred.
+pub fn probe_root(tablebase: &dyn Tablebase, pos: &Position) -> Option<Vec<Move>> {
+    if !can_probe(tablebase, pos) {
+        return None;
+    }
+
+    let mut ranked = Vec::new();
+
+    for mv in MoveGen::legals(pos) {
+        let mut child = pos.clone();
+        child.make_move(&mv);
+
+        let wdl = -tablebase.probe_wdl(&child)?;
+        let zeroing = mv.flags().is_capture() || mv.piece() == Piece::Pawn;
+
+        // the child's DTM is negative when we win, so the highest is best
+        let rank = match tablebase.probe_dtm(&child) {
+            Some(dtm) => dtm,
+            None => match (wdl, tablebase.probe_dtz(&child).map(i32::abs)) {
+                (Wdl::Win | Wdl::CursedWin, _) if zeroing => 0,
+                (Wdl::Win | Wdl::CursedWin, Some(dtz)) => -dtz,
+                (Wdl::Loss | Wdl::BlessedLoss, Some(dtz)) => dtz,
+                _ => 0,
+            },
+        };
+
+        ranked.push((mv, wdl, rank));
+    }
+
+    let best = ranked.iter().map(|&(_, wdl, rank)| (wdl, rank)).max()?;
+
+    Some(
+        ranked
+            .into_iter()
+            .filter(|&(_, wdl, rank)| (wdl, rank) == best)
+            .map(|(mv, _, _)| mv)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use psce_core::Color;
+
+    use super::*;
+    use crate::{SearchOptions, search};
+
+    /// Scores positions by the material balance after a capture by the side
+    /// to move, which is exact for the positions used below.
+    #[derive(Debug)]
+    struct MaterialTablebase;
+
+    impl Tablebase for MaterialTablebase {
+        fn max_pieces(&self) -> usize {
+            3
+        }
+
+        fn probe_wdl(&self, pos: &Position) -> Option<Wdl> {
+            let count = |color| pos.bitboards().color(color).count();
+            let us = count(pos.side_to_move());
+            let can_capture = MoveGen::legals(pos).iter().any(|m| m.flags().is_capture());
+            let them = count(!pos.side_to_move()) - can_capture as u8;
+
+            Some(match us.cmp(&them) {
+                std::cmp::Ordering::Greater => Wdl::Win,
+                std::cmp::Ordering::Less => Wdl::Loss,
+                std::cmp::Ordering::Equal => Wdl::Draw,
+            })
+        }
+
+        fn probe_dtz(&self, _pos: &Position) -> Option<i32> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_probe_root_keeps_the_win() {
+        // the rook on d2 is attacked by the black king
+        let pos = Position::from_fen("8/8/8/8/8/2k5/3R4/6K1 w - - 0 1").unwrap();
+        let moves = probe_root(&MaterialTablebase, &pos).unwrap();
+
+        assert!(!moves.is_empty());
+
+        for mv in moves {
+            let mut child = pos.clone();
+            child.make_move(&mv);
+
+            let king = child.king_square(Color::Black).unwrap();
+            let rook = child.bitboards().piece(Color::White, Piece::Rook);
+
+            assert!(
+                !MoveGen::legals(&child)
+                    .iter()
+                    .any(|m| { m.from() == king && rook.get(m.to()) })
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_probes_after_captures() {
+        let pos = Position::from_fen("8/8/8/3k4/8/8/3r4/3QK3 w - - 0 1").unwrap();
+        let options = SearchOptions {
+            depth: 3,
+            tablebase: Some(Arc::new(MaterialTablebase)),
+            ..Default::default()
+        };
+
+        let result = search(&pos, &options).unwrap();
+
+        assert!(result.stats.tb_hits > 0);
+    }
+
+    #[test]
+    fn test_not_probed_above_max_pieces() {
+        assert!(probe_root(&MaterialTablebase, &Position::initial()).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    fmt::{self, Debug},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use psce_core::PackedMove;
+
+use crate::eval::is_mate_score;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub mv: Option<PackedMove>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+/// A slot stores the key xor'ed with the data so that torn writes from
+/// concurrent threads are detected as misses instead of corrupt entries.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Transposition table shared lock-free between search threads.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    pub fn new(mb: usize) -> Self {
+        let len = (mb * 1024 * 1024 / size_of::<Slot>()).max(1);
+
+        Self {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    /// Forgets all entries, as before a new game.
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, hash: u64) -> &Slot {
+        &self.slots[((hash as u128 * self.slots.len() as u128) >> 64) as usize]
+    }
+
+    /// Looks up `hash`, adjusting mate scores to be relative to `ply`.
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<Entry> {
+        let slot = self.slot(hash);
+        let data = slot.data.load(Ordering::Relaxed);
+        let key = slot.key.load(Ordering::Relaxed);
+
+        if data == 0 || key ^ data != hash {
+            return None;
+        }
+
+        let mv = (data & 0xFFFF) as u16;
+        let score = (data >> 16) as u32 as i32;
+
+        Some(Entry {
+            mv: (mv != 0).then(|| PackedMove::new(mv)),
+            score: score_from_tt(score, ply),
+            depth: (data >> 48) as u8,
+            bound: match (data >> 56) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+        })
+    }
+
+    pub fn store(&self, hash: u64, ply: usize, entry: Entry) {
+        let bound = match entry.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+
+        let data = entry.mv.map_or(0, PackedMove::to_repr) as u64
+            | (score_to_tt(entry.score, ply) as u32 as u64) << 16
+            | (entry.depth as u64) << 48
+            | bound << 56
+            | 1 << 58;
+
+        let slot = self.slot(hash);
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+impl Debug for TranspositionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranspositionTable")
+            .field("slots", &self.slots.len())
+            .finish()
+    }
+}
+
+// mate scores are stored relative to the node rather than the root
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if !is_mate_score(score) {
+        score
+    } else if score > 0 {
+        score + ply as i32
+    } else {
+        score - ply as i32
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if !is_mate_score(score) {
+        score
+    } else if score > 0 {
+        score - ply as i32
+    } else {
+        score + ply as i32
+    }
+}
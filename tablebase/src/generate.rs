@@ -0,0 +1,216 @@
+use psce_core::{Color, Piece, Position, Rank, Square};
+use psce_movegen::MoveGen;
+
+use crate::{
+    material::Material,
+    table::{DRAW, INVALID, Outcome, Table},
+};
+
+/// Marks positions that have a move to a draw or a win and can never be
+/// lost.
+const CANNOT_LOSE: u8 = u8::MAX;
+
+/// Generates `material` by retrograde analysis. Captures and promotions
+/// leave the table and are resolved with `probe`, which must know every
+/// successor endgame.
+///
+/// Positions are resolved ply by ply: a position is won in `n + 1` plies if
+/// a move leads to a position lost in `n`, and lost in `n + 1` once all of
+/// its moves lead to positions won in at most `n`. The unmove generator
+/// finds the predecessors to update whenever a position is resolved.
+pub fn generate(material: Material, probe: impl Fn(&Position) -> Outcome) -> Table {
+    let mut table = Table::new(material);
+    let len = table.len();
+
+    // distinct in-table successors not yet known to win for the opponent
+    let mut remaining = vec![0u8; len];
+    // longest opponent win reachable by leaving the table
+    let mut longest_exit = vec![0u8; len];
+    let mut buckets: Vec<Vec<u32>> = Vec::new();
+
+    for index in 0..len {
+        let (squares, side_to_move) = table.squares(index);
+
+        let Some(pos) = table.position(&squares, side_to_move) else {
+            table.values[index] = INVALID;
+            continue;
+        };
+
+        let moves = MoveGen::legals(&pos);
+
+        if moves.is_empty() {
+            if is_in_check(&pos) {
+                schedule(&mut buckets, 0, index);
+            } else {
+                remaining[index] = CANNOT_LOSE;
+            }
+
+            continue;
+        }
+
+        let mut successors = Vec::new();
+        let mut can_lose = true;
+
+        for mv in moves {
+            let mut child = pos.clone();
+            child.make_move(&mv);
+
+            if mv.is_quiet() {
+                successors.push(table.index(&child));
+                continue;
+            }
+
+            match probe(&child) {
+                Outcome::Loss(plies) => {
+                    schedule(&mut buckets, plies as usize + 1, index);
+                    can_lose = false;
+                }
+                Outcome::Win(plies) => {
+                    longest_exit[index] = longest_exit[index].max(plies);
+                }
+                Outcome::Draw => can_lose = false,
+            }
+        }
+
+        successors.sort_unstable();
+        successors.dedup();
+
+        if !can_lose {
+            remaining[index] = CANNOT_LOSE;
+        } else if successors.is_empty() {
+            schedule(&mut buckets, longest_exit[index] as usize + 1, index);
+        } else {
+            remaining[index] = successors.len() as u8;
+        }
+    }
+
+    let mut plies = 0;
+
+    while plies < buckets.len() {
+        let bucket = std::mem::take(&mut buckets[plies]);
+
+        for index in bucket {
+            let index = index as usize;
+
+            if table.values[index] != DRAW {
+                continue;
+            }
+
+            table.values[index] = plies as u8 + 1;
+
+            for predecessor in predecessors(&table, index) {
+                if table.values[predecessor] != DRAW {
+                    continue;
+                }
+
+                if plies % 2 == 0 {
+                    // a move to a lost position wins
+                    schedule(&mut buckets, plies + 1, predecessor);
+                } else if remaining[predecessor] != CANNOT_LOSE {
+                    remaining[predecessor] -= 1;
+
+                    if remaining[predecessor] == 0 {
+                        let longest = plies.max(longest_exit[predecessor] as usize);
+                        schedule(&mut buckets, longest + 1, predecessor);
+                    }
+                }
+            }
+        }
+
+        plies += 1;
+    }
+
+    table
+}
+
+/// Canonical indices of the positions with a non-capturing, non-promoting
+/// move to the position at `index`.
+fn predecessors(table: &Table, index: usize) -> Vec<usize> {
+    let (squares, side_to_move) = table.squares(index);
+    let mover = !side_to_move;
+    let pieces = table.material().pieces();
+
+    // with the mover to move, the quiet piece moves are exactly the unmoves
+    let placed: Vec<_> = pieces
+        .iter()
+        .zip(&squares)
+        .map(|(&(color, piece), &square)| (color, piece, square))
+        .collect();
+    let unmove_pos = Position::from_pieces(&placed, mover);
+    let occupied = unmove_pos.bitboards().all();
+
+    let mut unmoves: Vec<(usize, Square)> = MoveGen::pseudo_legals(&unmove_pos)
+        .into_iter()
+        .filter(|mv| mv.piece() != Piece::Pawn && !mv.flags().is_capture())
+        .map(|mv| {
+            (
+                squares.iter().position(|&sq| sq == mv.from()).unwrap(),
+                mv.to(),
+            )
+        })
+        .collect();
+
+    for (i, &(color, piece)) in pieces.iter().enumerate() {
+        if color != mover || piece != Piece::Pawn {
+            continue;
+        }
+
+        let (back, start_rank) = match color {
+            Color::White => (-1, Rank::Second),
+            Color::Black => (1, Rank::Seventh),
+        };
+
+        // pawns never stand on the first or last rank
+        let Some(single) = squares[i]
+            .offset(back, 0)
+            .filter(|sq| !matches!(sq.rank(), Rank::First | Rank::Eighth))
+        else {
+            continue;
+        };
+
+        if occupied.get(single) {
+            continue;
+        }
+
+        unmoves.push((i, single));
+
+        // a pawn on its fourth rank may have made a double push
+        if squares[i].rank().distance(start_rank) == 2
+            && let Some(double) = single.offset(back, 0)
+            && !occupied.get(double)
+        {
+            unmoves.push((i, double));
+        }
+    }
+
+    let mut predecessors: Vec<_> = unmoves
+        .into_iter()
+        .filter_map(|(i, from)| {
+            let mut previous = squares.clone();
+            previous[i] = from;
+
+            table.placement(&previous, mover)?;
+
+            Some(table.index_of(&previous, mover))
+        })
+        .collect();
+
+    predecessors.sort_unstable();
+    predecessors.dedup();
+    predecessors
+}
+
+fn schedule(buckets: &mut Vec<Vec<u32>>, plies: usize, index: usize) {
+    if buckets.len() <= plies {
+        buckets.resize_with(plies + 1, Vec::new);
+    }
+
+    buckets[plies].push(index as u32);
+}
+
+fn is_in_check(pos: &Position) -> bool {
+    let color = pos.side_to_move();
+    let king_square = pos.king_square(color).unwrap();
+
+    MoveGen::is_attacked(pos, king_square, !color)
+}
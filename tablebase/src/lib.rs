@@ -0,0 +1,314 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use psce_core::{Castling, Color, Piece, Position, Variant};
+use psce_search::{Tablebase, Wdl};
+
+mod generate;
+mod material;
+mod table;
+
+pub use material::Material;
+pub use table::{Outcome, Table};
+
+const EXTENSION: &str = "pstb";
+
+/// Endgame tables generated in memory by retrograde analysis, storing the
+/// distance to mate of every position.
+#[derive(Default, Debug)]
+pub struct Tablebases {
+    tables: HashMap<Material, Table>,
+}
+
+impl Tablebases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates the endgame `name` (like `KRvKP`) and every endgame it can
+    /// reach by captures and promotions.
+    pub fn generate(&mut self, name: &str) -> Result<(), String> {
+        self.generate_material(Material::parse(name)?);
+
+        Ok(())
+    }
+
+    fn generate_material(&mut self, material: Material) {
+        if material.len() <= 2
+            || self.tables.contains_key(&material)
+            || self.tables.contains_key(&material.flipped())
+        {
+            return;
+        }
+
+        for successor in material.successors() {
+            self.generate_material(successor);
+        }
+
+        let table = generate::generate(material.clone(), |pos| {
+            self.probe(pos)
+                .expect("successor endgames are generated first")
+        });
+
+        self.tables.insert(material, table);
+    }
+
+    /// Loads the tables saved in `dir`, ignoring files of other kinds.
+    pub fn load(&mut self, dir: &Path) -> Result<(), String> {
+        let entries = fs::read_dir(dir).map_err(|e| {
+            format!(
+                "Failed to read tablebase directory {}: {}",
+                dir.display(),
+                e
+            )
+        })?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                continue;
+            }
+
+            let bytes =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let table =
+                Table::from_bytes(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+            self.tables.insert(table.material().clone(), table);
+        }
+
+        Ok(())
+    }
+
+    /// Saves every table to `dir` as `<endgame>.pstb`.
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        for (material, table) in &self.tables {
+            let path = dir.join(format!("{}.{}", material, EXTENSION));
+
+            fs::write(&path, table.to_bytes())
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(&Material::parse(name).ok()?)
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    /// Looks up `pos` in the table for its material, swapping the colors if
+    /// only the other side's table is generated.
+    pub fn probe(&self, pos: &Position) -> Option<Outcome> {
+        if pos.castling() != Castling::NONE || pos.variant() != Variant::Standard {
+            return None;
+        }
+
+        let material = Material::of(pos);
+
+        if material.len() == 2 {
+            return Some(Outcome::Draw);
+        }
+
+        if let Some(table) = self.tables.get(&material) {
+            return table.probe(pos);
+        }
+
+        self.tables.get(&material.flipped())?.probe(&flip(pos))
+    }
+}
+
+impl Tablebase for Tablebases {
+    fn max_pieces(&self) -> usize {
+        self.tables.keys().map(Material::len).max().unwrap_or(0)
+    }
+
+    fn probe_wdl(&self, pos: &Position) -> Option<Wdl> {
+        Some(match self.probe(pos)? {
+            Outcome::Win(_) => Wdl::Win,
+            Outcome::Draw => Wdl::Draw,
+            Outcome::Loss(_) => Wdl::Loss,
+        })
+    }
+
+    /// The tables only store the distance to mate.
+    fn probe_dtz(&self, _pos: &Position) -> Option<i32> {
+        None
+    }
+
+    fn probe_dtm(&self, pos: &Position) -> Option<i32> {
+        Some(match self.probe(pos)? {
+            Outcome::Win(plies) => plies as i32,
+            Outcome::Draw => 0,
+            Outcome::Loss(plies) => -(plies as i32),
+        })
+    }
+}
+
+/// Mirrors `pos` vertically and swaps the colors.
+fn flip(pos: &Position) -> Position {
+    let mut pieces = Vec::new();
+
+    for color in Color::ALL {
+        for piece in Piece::ALL {
+            for square in pos.bitboards().piece(color, piece).squares() {
+                pieces.push((!color, piece, square.mirror()));
+            }
+        }
+    }
+
+    Position::from_pieces(&pieces, !pos.side_to_move())
+}
+
+#[cfg(test)]
+mod tests {
+    use psce_search::{SearchOptions, search};
+
+    use super::*;
+
+    fn longest_win(table: &Table) -> u8 {
+        table
+            .outcomes()
+            .filter(|&(color, _)| color == Color::White)
+            .filter_map(|(_, outcome)| match outcome {
+                Outcome::Win(_) => outcome.moves(),
+                _ => None,
+            })
+            .max()
+            .unwrap()
+    }
+
+    fn probe(tablebases: &Tablebases, fen: &str) -> Outcome {
+        tablebases.probe(&Position::from_fen(fen).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_kqk() {
+        let mut tablebases = Tablebases::new();
+        tablebases.generate("KQvK").unwrap();
+
+        assert_eq!(longest_win(tablebases.table("KQvK").unwrap()), 10);
+        assert_eq!(
+            probe(&tablebases, "7k/5Q2/6K1/8/8/8/8/8 w - - 0 1"),
+            Outcome::Win(1)
+        );
+        assert_eq!(
+            probe(&tablebases, "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"),
+            Outcome::Loss(0)
+        );
+        // stalemate
+        assert_eq!(
+            probe(&tablebases, "7k/5Q2/5K2/8/8/8/8/8 b - - 0 1"),
+            Outcome::Draw
+        );
+        // the same endgame with colors swapped
+        assert_eq!(
+            probe(&tablebases, "8/8/8/8/8/6k1/5q2/7K b - - 0 1"),
+            Outcome::Win(1)
+        );
+    }
+
+    #[test]
+    fn test_krk() {
+        let mut tablebases = Tablebases::new();
+        tablebases.generate("KRvK").unwrap();
+
+        assert_eq!(longest_win(tablebases.table("KRvK").unwrap()), 16);
+        // black to move captures the rook
+        assert_eq!(
+            probe(&tablebases, "8/8/8/8/8/8/1k6/1R2K3 b - - 0 1"),
+            Outcome::Draw
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("psce-tablebase-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut tablebases = Tablebases::new();
+        tablebases.generate("KQvK").unwrap();
+        tablebases.save(&dir).unwrap();
+
+        let mut loaded = Tablebases::new();
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let table = tablebases.table("KQvK").unwrap();
+        assert_eq!(loaded.table("KQvK").unwrap().values, table.values);
+        assert_eq!(
+            probe(&loaded, "7k/5Q2/6K1/8/8/8/8/8 w - - 0 1"),
+            Outcome::Win(1)
+        );
+    }
+
+    #[test]
+    fn test_search_plays_tablebase_moves() {
+        let mut tablebases = Tablebases::new();
+        tablebases.generate("KQvK").unwrap();
+
+        let pos = Position::from_fen("7k/8/5K2/8/8/8/8/Q7 w - - 0 1").unwrap();
+        let options = SearchOptions {
+            depth: 1,
+            tablebase: Some(std::sync::Arc::new(tablebases)),
+            ..Default::default()
+        };
+
+        let result = search(&pos, &options).unwrap();
+        let mut child = pos.clone();
+        child.make_move(&result.pv[0]);
+
+        // only moves that keep the shortest mate are searched
+        let tablebases = options.tablebase.as_ref().unwrap();
+        assert_eq!(tablebases.probe_dtm(&child), Some(-2));
+    }
+
+    #[test]
+    fn test_kpk() {
+        let mut tablebases = Tablebases::new();
+        tablebases.generate("KPvK").unwrap();
+
+        let table = tablebases.table("KPvK").unwrap();
+        let (mut wins, mut draws) = (0, 0);
+
+        for (color, outcome) in table.outcomes() {
+            match (color, outcome) {
+                (Color::White, Outcome::Win(_)) => wins += 1,
+                (Color::White, Outcome::Draw) => draws += 1,
+                _ => {}
+            }
+        }
+
+        // the published totals for white to move, each canonical position
+        // standing for itself and its mirror on the other wing
+        assert_eq!(2 * wins, 124_960);
+        assert_eq!(2 * draws, 38_368);
+        assert_eq!(longest_win(table), 28);
+        // stalemate with the king in front of its pawn
+        assert_eq!(
+            probe(&tablebases, "4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"),
+            Outcome::Draw
+        );
+        // rook pawn with the defending king in the corner
+        assert_eq!(
+            probe(&tablebases, "k7/8/8/8/8/8/P7/K7 w - - 0 1"),
+            Outcome::Draw
+        );
+        assert!(matches!(
+            probe(&tablebases, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"),
+            Outcome::Loss(_)
+        ));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_kbnk() {
+        let mut tablebases = Tablebases::new();
+        tablebases.generate("KBNvK").unwrap();
+
+        assert_eq!(longest_win(tablebases.table("KBNvK").unwrap()), 33);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::Display;
+
+use psce_core::{Color, Piece, Position};
+
+const ORDER: [Piece; 6] = [
+    Piece::King,
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
+/// The pieces of an endgame, named like `KRvKP`: white's pieces, then
+/// black's, each side ordered king, queen, rook, bishop, knight, pawn.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Material {
+    pieces: Vec<(Color, Piece)>,
+}
+
+impl Material {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let (white, black) = name
+            .split_once('v')
+            .ok_or_else(|| format!("Invalid endgame: {}", name))?;
+
+        let mut pieces = Vec::new();
+
+        for (color, side) in [(Color::White, white), (Color::Black, black)] {
+            if side.chars().filter(|&c| c == 'K').count() != 1 {
+                return Err(format!("Invalid endgame: {}", name));
+            }
+
+            for c in side.chars() {
+                let piece = Piece::from_char(c)
+                    .filter(|_| c.is_ascii_uppercase())
+                    .ok_or_else(|| format!("Invalid piece in endgame: {}", c))?;
+
+                pieces.push((color, piece));
+            }
+        }
+
+        let material = Self::new(pieces);
+
+        if material.pawns(Color::White) && material.pawns(Color::Black) {
+            return Err("Endgames with pawns on both sides are not supported".to_string());
+        }
+
+        Ok(material)
+    }
+
+    pub fn of(pos: &Position) -> Self {
+        let mut pieces = Vec::new();
+
+        for color in Color::ALL {
+            for piece in ORDER {
+                for _ in 0..pos.bitboards().piece(color, piece).count() {
+                    pieces.push((color, piece));
+                }
+            }
+        }
+
+        Self { pieces }
+    }
+
+    fn new(mut pieces: Vec<(Color, Piece)>) -> Self {
+        pieces.sort_by_key(|&(color, piece)| {
+            (color as usize, ORDER.iter().position(|&p| p == piece))
+        });
+
+        Self { pieces }
+    }
+
+    /// Pieces in index order: white's, then black's.
+    pub fn pieces(&self) -> &[(Color, Piece)] {
+        &self.pieces
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    pub fn pawns(&self, color: Color) -> bool {
+        self.pieces.contains(&(color, Piece::Pawn))
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.pawns(Color::White) || self.pawns(Color::Black)
+    }
+
+    /// The same endgame with the colors swapped.
+    pub fn flipped(&self) -> Self {
+        Self::new(
+            self.pieces
+                .iter()
+                .map(|&(color, piece)| (!color, piece))
+                .collect(),
+        )
+    }
+
+    /// Endgames reachable by a capture or a promotion.
+    pub fn successors(&self) -> Vec<Material> {
+        let mut successors = Vec::new();
+
+        for (index, &(color, piece)) in self.pieces.iter().enumerate() {
+            if piece == Piece::King {
+                continue;
+            }
+
+            let mut captured = self.pieces.clone();
+            captured.remove(index);
+            successors.push(Self::new(captured));
+
+            if piece == Piece::Pawn {
+                for promotion in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+                    let mut promoted = self.pieces.clone();
+                    promoted[index] = (color, promotion);
+                    successors.push(Self::new(promoted.clone()));
+
+                    // promotions may also capture
+                    for other in 0..promoted.len() {
+                        if promoted[other].0 != color && promoted[other].1 != Piece::King {
+                            let mut captured = promoted.clone();
+                            captured.remove(other);
+                            successors.push(Self::new(captured));
+                        }
+                    }
+                }
+            }
+        }
+
+        successors.sort_by_key(|m| m.to_string());
+        successors.dedup();
+        successors
+    }
+}
+
+impl Display for Material {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for color in Color::ALL {
+            if color == Color::Black {
+                write!(f, "v")?;
+            }
+
+            for &(_, piece) in self.pieces.iter().filter(|(c, _)| *c == color) {
+                write!(f, "{}", piece.to_char(Color::White))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let material = Material::parse("KRvKP").unwrap();
+
+        assert_eq!(material.to_string(), "KRvKP");
+        assert_eq!(material.flipped().to_string(), "KPvKR");
+        assert_eq!(
+            Material::of(&Position::from_fen("8/8/8/3k4/8/8/3p4/3RK3 w - - 0 1").unwrap()),
+            material
+        );
+
+        assert!(Material::parse("KPvKP").is_err());
+        assert!(Material::parse("KRKP").is_err());
+    }
+
+    #[test]
+    fn test_successors() {
+        let names: Vec<_> = Material::parse("KRvKP")
+            .unwrap()
+            .successors()
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "KRvK", "KRvKB", "KRvKN", "KRvKQ", "KRvKR", "KvKB", "KvKN", "KvKP", "KvKQ", "KvKR"
+            ]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use psce_core::{Color, File, Piece, Position, Rank, Square};
+use psce_movegen::MoveGen;
+
+use crate::material::Material;
+
+/// Stored value of positions that are drawn (or not yet resolved during
+/// generation); other values are the distance to mate in plies plus one.
+pub(crate) const DRAW: u8 = 0;
+pub(crate) const INVALID: u8 = u8::MAX;
+
+const MAGIC: [u8; 4] = *b"PSTB";
+
+/// Distance to mate in plies from the point of view of the side to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win(u8),
+    Draw,
+    Loss(u8),
+}
+
+impl Outcome {
+    pub(crate) fn from_value(value: u8) -> Option<Self> {
+        match value {
+            INVALID => None,
+            DRAW => Some(Self::Draw),
+            value if value % 2 == 0 => Some(Self::Win(value - 1)),
+            value => Some(Self::Loss(value - 1)),
+        }
+    }
+
+    /// Full moves until mate, as commonly used to describe endgames.
+    pub fn moves(self) -> Option<u8> {
+        match self {
+            Self::Win(plies) => Some(plies.div_ceil(2)),
+            Self::Loss(plies) => Some(plies / 2),
+            Self::Draw => None,
+        }
+    }
+}
+
+/// Squares the white king is mapped to: the a1-d1-d4 triangle for pawnless
+/// endgames and the queen side for endgames with pawns.
+fn king_slots(pawnless: bool) -> Vec<Square> {
+    Square::all()
+        .filter(|&sq| sq.file() <= File::D && (!pawnless || sq.rank().index() <= sq.file().index()))
+        .collect()
+}
+
+fn transform(square: Square, symmetry: usize) -> Square {
+    let mut square = square;
+
+    if symmetry & 1 != 0 {
+        square = square.flip();
+    }
+
+    if symmetry & 2 != 0 {
+        square = square.mirror();
+    }
+
+    // reflection in the a1-h8 diagonal
+    if symmetry & 4 != 0 {
+        square = Square::from_rf(
+            Rank::ALL[square.file().index()],
+            File::ALL[square.rank().index()],
+        );
+    }
+
+    square
+}
+
+/// A generated endgame with one value per index.
+///
+/// The index is made of the side to move, the white king's slot, the black
+/// king's square and the squares of the remaining pieces. Positions are
+/// reduced by symmetry to a canonical form, indices of other forms are
+/// invalid.
+#[derive(Debug)]
+pub struct Table {
+    material: Material,
+    slots: Vec<Square>,
+    slot_of: [u8; 64],
+    pub(crate) values: Vec<u8>,
+}
+
+impl Table {
+    pub(crate) fn new(material: Material) -> Self {
+        let slots = king_slots(!material.has_pawns());
+        let mut slot_of = [u8::MAX; 64];
+
+        for (slot, &square) in slots.iter().enumerate() {
+            slot_of[square.index()] = slot as u8;
+        }
+
+        let len = 2 * slots.len() * 64usize.pow(material.len() as u32 - 1);
+
+        Self {
+            material,
+            slots,
+            slot_of,
+            values: vec![DRAW; len],
+        }
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Looks up a position with exactly this table's material.
+    pub fn probe(&self, pos: &Position) -> Option<Outcome> {
+        Outcome::from_value(self.values[self.index(pos)])
+    }
+
+    /// The side to move and outcome of every legal position in canonical
+    /// form.
+    pub fn outcomes(&self) -> impl Iterator<Item = (Color, Outcome)> + '_ {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &value)| {
+                let outcome = Outcome::from_value(value)?;
+
+                Some((self.squares(index).1, outcome))
+            })
+    }
+
+    /// Serializes the table: a magic, the endgame name and the values as
+    /// runs of a LEB128 length followed by the value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.material.to_string();
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+
+        for run in self.values.chunk_by(|a, b| a == b) {
+            let mut len = run.len();
+
+            while len >= 0x80 {
+                bytes.push(len as u8 | 0x80);
+                len >>= 7;
+            }
+
+            bytes.push(len as u8);
+            bytes.push(run[0]);
+        }
+
+        bytes
+    }
+
+    /// Reads a table serialized by [`Table::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid tablebase file".to_string();
+
+        let rest = bytes.strip_prefix(&MAGIC).ok_or_else(invalid)?;
+        let (&name_len, rest) = rest.split_first().ok_or_else(invalid)?;
+        let name = rest.get(..name_len as usize).ok_or_else(invalid)?;
+        let name = std::str::from_utf8(name).map_err(|_| invalid())?;
+
+        let mut table = Self::new(Material::parse(name)?);
+        let mut rest = rest[name_len as usize..].iter();
+        let mut index = 0usize;
+
+        while let Some(&byte) = rest.next() {
+            let mut len = (byte & 0x7f) as usize;
+            let mut shift = 7;
+            let mut byte = byte;
+
+            while byte & 0x80 != 0 {
+                byte = *rest.next().ok_or_else(invalid)?;
+                len |= ((byte & 0x7f) as usize)
+                    .checked_shl(shift)
+                    .ok_or_else(invalid)?;
+                shift += 7;
+            }
+
+            let value = *rest.next().ok_or_else(invalid)?;
+            let end = index.checked_add(len).ok_or_else(invalid)?;
+
+            table
+                .values
+                .get_mut(index..end)
+                .ok_or_else(invalid)?
+                .fill(value);
+            index = end;
+        }
+
+        if index != table.len() {
+            return Err(invalid());
+        }
+
+        Ok(table)
+    }
+
+    pub(crate) fn index(&self, pos: &Position) -> usize {
+        let mut squares = Vec::with_capacity(self.material.len());
+        let mut remaining = pos.bitboards().clone();
+
+        for &(color, piece) in self.material.pieces() {
+            let square = remaining.piece_mut(color, piece).pop_square().unwrap();
+            squares.push(square);
+        }
+
+        self.index_of(&squares, pos.side_to_move())
+    }
+
+    /// Index of the canonical form of `squares`, given in material order.
+    pub(crate) fn index_of(&self, squares: &[Square], side_to_move: Color) -> usize {
+        let squares = self.canonical(squares);
+
+        let mut index =
+            side_to_move as usize * self.slots.len() + self.slot_of[squares[0].index()] as usize;
+
+        for &square in &squares[1..] {
+            index = index * 64 + square.index();
+        }
+
+        index
+    }
+
+    /// Picks the smallest of the symmetric forms of `squares`, with the
+    /// squares of identical pieces sorted.
+    fn canonical(&self, squares: &[Square]) -> Vec<Square> {
+        let symmetries: &[usize] = if self.material.has_pawns() {
+            &[0, 1]
+        } else {
+            &[0, 1, 2, 3, 4, 5, 6, 7]
+        };
+
+        symmetries
+            .iter()
+            .map(|&symmetry| {
+                let mut transformed: Vec<_> =
+                    squares.iter().map(|&sq| transform(sq, symmetry)).collect();
+                self.sort_identical(&mut transformed);
+                transformed
+            })
+            .min()
+            .unwrap()
+    }
+
+    fn sort_identical(&self, squares: &mut [Square]) {
+        let pieces = self.material.pieces();
+        let mut start = 0;
+
+        while start < pieces.len() {
+            let end = (start..pieces.len())
+                .find(|&i| pieces[i] != pieces[start])
+                .unwrap_or(pieces.len());
+
+            squares[start..end].sort_unstable();
+            start = end;
+        }
+    }
+
+    /// Decodes the squares and side to move of `index`.
+    pub(crate) fn squares(&self, index: usize) -> (Vec<Square>, Color) {
+        let mut index = index;
+        let mut squares = vec![Square::A1; self.material.len()];
+
+        for square in squares[1..].iter_mut().rev() {
+            *square = Square::new((index % 64) as u8).unwrap();
+            index /= 64;
+        }
+
+        squares[0] = self.slots[index % self.slots.len()];
+
+        let side_to_move = if index / self.slots.len() == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        (squares, side_to_move)
+    }
+
+    /// Builds the position of `squares`, or `None` if it is not a legal
+    /// position in canonical form.
+    pub(crate) fn position(&self, squares: &[Square], side_to_move: Color) -> Option<Position> {
+        if self.canonical(squares) != squares {
+            return None;
+        }
+
+        self.placement(squares, side_to_move)
+    }
+
+    /// Builds the position of `squares`, or `None` if it is not legal.
+    pub(crate) fn placement(&self, squares: &[Square], side_to_move: Color) -> Option<Position> {
+        let pieces = self.material.pieces();
+
+        for (i, &square) in squares.iter().enumerate() {
+            if squares[..i].contains(&square) {
+                return None;
+            }
+
+            if pieces[i].1 == Piece::Pawn && matches!(square.rank(), Rank::First | Rank::Eighth) {
+                return None;
+            }
+        }
+
+        let placed: Vec<_> = pieces
+            .iter()
+            .zip(squares)
+            .map(|(&(color, piece), &square)| (color, piece, square))
+            .collect();
+
+        let pos = Position::from_pieces(&placed, side_to_move);
+
+        // the side that just moved cannot be in check
+        let king = pos.king_square(!side_to_move).unwrap();
+
+        (!MoveGen::is_attacked(&pos, king, side_to_move)).then_some(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetric_positions_share_an_index() {
+        let table = Table::new(Material::parse("KRvK").unwrap());
+
+        let fens = [
+            "8/8/8/8/8/1k6/8/K6R w - - 0 1",
+            "8/8/8/8/8/6k1/8/R6K w - - 0 1",
+            "K6R/8/1k6/8/8/8/8/8 w - - 0 1",
+            "R7/8/8/8/8/8/2k5/K7 w - - 0 1",
+        ];
+
+        let indices: Vec<_> = fens
+            .iter()
+            .map(|fen| table.index(&Position::from_fen(fen).unwrap()))
+            .collect();
+
+        assert!(indices.iter().all(|&index| index == indices[0]));
+
+        let (squares, side_to_move) = table.squares(indices[0]);
+        assert!(table.position(&squares, side_to_move).is_some());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut table = Table::new(Material::parse("KRvK").unwrap());
+        table.values[..1000].fill(INVALID);
+        table.values[5000] = 33;
+
+        let bytes = table.to_bytes();
+        assert!(bytes.len() < 32);
+
+        let read = Table::from_bytes(&bytes).unwrap();
+        assert_eq!(read.material(), table.material());
+        assert_eq!(read.values, table.values);
+
+        assert!(Table::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Table::from_bytes(b"PSTB").is_err());
+    }
+}
@@ -1,10 +1,28 @@
-use std::{io, time::Instant};
+use std::{env, io, time::Instant};
 
-use psce_core::Position;
+use psce_core::{Move, Position};
 use psce_movegen::MoveGen;
-use psce_search::{SearchResult, evaluate_position, find_best_move};
+use psce_search::{
+    MAX_SKILL_LEVEL, SearchOptions, SearchResult, Skill, evaluate_position, format_score, search,
+};
 
 fn main() {
+    // an optional skill level from 0 to 20 weakens the engine
+    let skill = match env::args().nth(1).map(|arg| arg.parse::<u8>()) {
+        Some(Ok(level)) if level <= MAX_SKILL_LEVEL => Skill::new(level),
+        Some(_) => {
+            println!("Usage: tui [skill level 0-{}]", MAX_SKILL_LEVEL);
+            return;
+        }
+        None => Skill::default(),
+    };
+
+    let options = SearchOptions {
+        depth: 6,
+        skill,
+        ..Default::default()
+    };
+
     let mut position = Position::initial();
     println!("{}", position);
 
@@ -16,9 +34,10 @@ fn main() {
         }
 
         let mut selected = None;
+        let mut passed = false;
 
         loop {
-            println!("Enter move (e.g. e2e4):");
+            println!("Enter move (e.g. e2e4), or pass to see the engine's threat:");
             let mut input = String::new();
             io::stdin().read_line(&mut input).unwrap();
 
@@ -27,21 +46,34 @@ fn main() {
                 break;
             }
 
-            let mv = legal_moves.iter().find(|m| m.to_string() == trimmed);
-            if let Some(mv) = mv {
-                selected = Some(mv);
+            if trimmed == "pass" {
+                if MoveGen::is_in_check(&position, position.side_to_move()) {
+                    println!("Cannot pass while in check");
+                    continue;
+                }
+
+                passed = true;
                 break;
-            } else {
-                println!("Invalid move!");
+            }
+
+            match Move::from_uci(&position, trimmed) {
+                Ok(mv) => {
+                    selected = Some(mv);
+                    break;
+                }
+                Err(e) => println!("Invalid move ({})", e),
             }
         }
 
-        let Some(mv) = selected else {
+        if passed {
+            position.make_null_move();
+        } else if let Some(mv) = selected {
+            position.make_move(&mv);
+        } else {
             println!("Bye!");
             break;
-        };
+        }
 
-        position.make_move(mv);
         println!("{}", position);
         println!("Eval: {}", evaluate_position(&position));
 
@@ -53,7 +85,8 @@ fn main() {
             score: engine_eval,
             pv: engine_pv,
             stats,
-        }) = find_best_move(&position, 6)
+            ..
+        }) = search(&position, &options)
         else {
             println!("Checkmate! {:?} wins!", !position.side_to_move());
             break;
@@ -64,7 +97,7 @@ fn main() {
         println!(
             "Engine move: {} ({})",
             engine_pv.first().unwrap(),
-            engine_eval
+            format_score(engine_eval)
         );
 
         println!(
@@ -84,9 +117,10 @@ fn main() {
         );
 
         println!(
-            " -- {} beta cutoffs ({}%)",
+            " -- {} beta cutoffs ({}%), {}% on first move",
             stats.beta_cutoffs,
-            (stats.beta_cutoffs as f64 / stats.nodes as f64 * 100.0) as u64
+            (stats.beta_cutoffs as f64 / stats.nodes as f64 * 100.0) as u64,
+            (stats.first_move_cutoffs as f64 / stats.beta_cutoffs as f64 * 100.0) as u64
         );
 
         position.make_move(engine_pv.first().unwrap());
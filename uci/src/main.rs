@@ -1,6 +1,331 @@
-use std::io::{self, BufRead};
+use std::{
+    io::{self, BufRead},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use psce_core::{Color, Move, Position, Variant};
+use psce_search::{
+    Book, BookSelection, Bound, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO, PolyglotKeys, SearchOptions,
+    Skill, Syzygy, TranspositionTable, format_score, search,
+};
+
+mod time;
+
+use time::TimeControl;
+
+const MAX_DEPTH: u8 = 64;
+
+struct Engine {
+    position: Position,
+    options: SearchOptions,
+    own_book: bool,
+    book: Option<Book>,
+    book_keys: Option<PolyglotKeys>,
+    search: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+    ponder: Option<Arc<AtomicBool>>,
+    skill_level: u8,
+    limit_strength: bool,
+    elo: u32,
+    chess960: bool,
+    variant: Variant,
+}
+
+impl Engine {
+    fn new() -> Self {
+        let options = SearchOptions::default();
+
+        Self {
+            position: Position::initial(),
+            options: SearchOptions {
+                tt: Some(Arc::new(TranspositionTable::new(options.hash_mb))),
+                ..options
+            },
+            own_book: false,
+            book: None,
+            book_keys: None,
+            search: None,
+            ponder: None,
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: MAX_ELO,
+            chess960: false,
+            variant: Variant::Standard,
+        }
+    }
+
+    fn stop(&mut self) {
+        self.ponder = None;
+
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            handle.join().unwrap();
+        }
+    }
+
+    /// Switches a ponder search to a normal timed search.
+    fn ponderhit(&mut self) {
+        if let Some(ponder) = self.ponder.take() {
+            ponder.store(false, Ordering::Relaxed);
+        }
+    }
+
+    fn set_option(&mut self, parts: &[&str]) {
+        let name_index = parts.iter().position(|&p| p == "name");
+        let value_index = parts.iter().position(|&p| p == "value");
+
+        let (Some(name_index), Some(value_index)) = (name_index, value_index) else {
+            println!("info string invalid setoption command");
+            return;
+        };
+
+        let name = parts[name_index + 1..value_index].join(" ");
+        let value = parts[value_index + 1..].join(" ");
+
+        match name.to_lowercase().as_str() {
+            "threads" => match value.parse() {
+                Ok(threads) => self.options.threads = threads,
+                Err(_) => println!("info string invalid Threads value: {}", value),
+            },
+            "hash" => match value.parse() {
+                Ok(hash_mb) => {
+                    self.options.hash_mb = hash_mb;
+                    self.options.tt = Some(Arc::new(TranspositionTable::new(hash_mb)));
+                }
+                Err(_) => println!("info string invalid Hash value: {}", value),
+            },
+            "multipv" => match value.parse() {
+                Ok(multi_pv) => self.options.multi_pv = multi_pv,
+                Err(_) => println!("info string invalid MultiPV value: {}", value),
+            },
+            "ponder" => {}
+            "skill level" => match value.parse() {
+                Ok(level) => self.skill_level = level,
+                Err(_) => println!("info string invalid Skill Level value: {}", value),
+            },
+            "uci_limitstrength" => self.limit_strength = value == "true",
+            "uci_elo" => match value.parse() {
+                Ok(elo) => self.elo = elo,
+                Err(_) => println!("info string invalid UCI_Elo value: {}", value),
+            },
+            "uci_chess960" => self.chess960 = value == "true",
+            "uci_variant" => match value.parse() {
+                Ok(variant) => self.variant = variant,
+                Err(e) => println!("info string {}", e),
+            },
+            "ownbook" => self.own_book = value == "true",
+            "bookfile" => match Book::load(&value) {
+                Ok(book) => self.book = Some(book),
+                Err(e) => println!("info string {}", e),
+            },
+            "bookkeys" => match PolyglotKeys::load(&value) {
+                Ok(keys) => self.book_keys = Some(keys),
+                Err(e) => println!("info string {}", e),
+            },
+            "syzygypath" if value.is_empty() || value == "<empty>" => {
+                self.options.tablebase = None;
+            }
+            "syzygypath" => match Syzygy::open(&value) {
+                Ok(syzygy) => {
+                    let (wdl, dtz) = syzygy.tables();
+                    println!("info string found {} WDL and {} DTZ tables", wdl, dtz);
+
+                    self.options.tablebase = Some(Arc::new(syzygy));
+                }
+                Err(e) => println!("info string {}", e),
+            },
+            _ => println!("info string unknown option: {}", name),
+        }
+    }
+
+    fn set_position(&mut self, parts: &[&str]) {
+        let moves_index = parts.iter().position(|&p| p == "moves");
+        let setup = &parts[1..moves_index.unwrap_or(parts.len())];
+
+        let position = match setup.first() {
+            Some(&"startpos") => Ok(Position::initial()),
+            Some(&"fen") => Position::from_fen(&setup[1..].join(" ")),
+            _ => Err("expected startpos or fen".to_string()),
+        };
+
+        let mut position = match position {
+            Ok(position) => position,
+            Err(e) => {
+                println!("info string invalid position: {}", e);
+                return;
+            }
+        };
+
+        // Chess960 positions are detected from the FEN, but a standard
+        // setup still needs king-takes-rook castling when the GUI asks for it
+        if self.chess960 {
+            position.set_chess960(true);
+        }
+
+        // crazyhouse and three-check show in the FEN, the others do not
+        if self.variant != Variant::Standard {
+            position.set_variant(self.variant);
+        }
+
+        for token in moves_index.map_or(&[][..], |i| &parts[i + 1..]) {
+            match Move::from_uci(&position, token) {
+                Ok(mv) => {
+                    position.make_move(&mv);
+                }
+                Err(e) => {
+                    println!("info string {}", e);
+                    return;
+                }
+            }
+        }
+
+        self.position = position;
+    }
+
+    /// `UCI_Elo` takes precedence over `Skill Level` when limiting strength.
+    fn skill(&self) -> Skill {
+        if self.limit_strength {
+            Skill::from_elo(self.elo)
+        } else {
+            Skill::new(self.skill_level)
+        }
+    }
+
+    /// Polyglot books only cover standard chess.
+    fn book_move(&self) -> Option<Move> {
+        if !self.own_book || self.position.variant() != Variant::Standard {
+            return None;
+        }
+
+        let (book, keys) = (self.book.as_ref()?, self.book_keys.as_ref()?);
+
+        book.pick(keys, &self.position, BookSelection::Weighted)
+    }
+
+    fn go(&mut self, parts: &[&str]) {
+        self.stop();
+
+        let chess960 = self.position.is_chess960();
+
+        let mut options = self.options.clone();
+        options.depth = MAX_DEPTH;
+        options.skill = self.skill();
+
+        let mut time_control = TimeControl::default();
+        let mut ponder = false;
+        let mut infinite = false;
+
+        let mut tokens = parts[1..].iter().peekable();
+        while let Some(&token) = tokens.next() {
+            if token == "searchmoves" {
+                while let Some(mv) = tokens
+                    .peek()
+                    .and_then(|t| Move::from_uci(&self.position, t).ok())
+                {
+                    options.search_moves.push(mv);
+                    tokens.next();
+                }
+
+                continue;
+            }
+
+            let mut value = || tokens.next().and_then(|v| v.parse::<u64>().ok());
+
+            match token {
+                "depth" => options.depth = value().unwrap_or(1).min(MAX_DEPTH as u64) as u8,
+                "nodes" => options.nodes = value(),
+                "movetime" => options.movetime = value().map(Duration::from_millis),
+                "wtime" => time_control.time[Color::White as usize] = value(),
+                "btime" => time_control.time[Color::Black as usize] = value(),
+                "winc" => time_control.increment[Color::White as usize] = value(),
+                "binc" => time_control.increment[Color::Black as usize] = value(),
+                "movestogo" => time_control.moves_to_go = value(),
+                "ponder" => ponder = true,
+                "infinite" => infinite = true,
+                _ => {}
+            }
+        }
+
+        // a pondering or infinite search must not answer before stop or
+        // ponderhit, so the book is only used for timed searches
+        if !ponder
+            && !infinite
+            && let Some(mv) = self.book_move()
+        {
+            println!("bestmove {}", mv.to_uci(chess960));
+            return;
+        }
+
+        if options.movetime.is_none() {
+            options.movetime = time_control.allocate(self.position.side_to_move());
+        }
+
+        // an infinite search ponders until stopped
+        if ponder || infinite {
+            let flag = Arc::new(AtomicBool::new(true));
+            options.ponder = Some(flag.clone());
+            self.ponder = ponder.then_some(flag);
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        options.stop = Some(stop.clone());
+
+        let position = self.position.clone();
+
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+
+            let Some(result) = search(&position, &options) else {
+                println!("bestmove 0000");
+                return;
+            };
+
+            let elapsed = start.elapsed();
+
+            for (index, line) in result.lines.iter().enumerate() {
+                let bound = match line.bound {
+                    Bound::Exact => "",
+                    Bound::Lower => " lowerbound",
+                    Bound::Upper => " upperbound",
+                };
+
+                println!(
+                    "info depth {} seldepth {} multipv {} score {}{} nodes {} time {} nps {} pv {}",
+                    result.depth,
+                    result.stats.seldepth,
+                    index + 1,
+                    format_score(line.score),
+                    bound,
+                    result.stats.nodes,
+                    elapsed.as_millis(),
+                    (result.stats.nodes as f64 / elapsed.as_secs_f64()) as u64,
+                    line.pv
+                        .iter()
+                        .map(|mv| mv.to_uci(chess960))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                );
+            }
+
+            let best = result.pv[0].to_uci(chess960);
+
+            match result.pv.get(1) {
+                Some(ponder) => println!("bestmove {} ponder {}", best, ponder.to_uci(chess960)),
+                None => println!("bestmove {}", best),
+            }
+        });
+
+        self.search = Some((stop, handle));
+    }
+}
 
 fn main() {
+    let mut engine = Engine::new();
+
     let stdin = io::stdin();
     for line in stdin.lock().lines() {
         let command = line.unwrap_or_else(|_| "quit".to_string());
@@ -10,21 +335,63 @@ fn main() {
         match parts.first() {
             Some(&"uci") => {
                 println!("id name psce");
+                println!("option name Threads type spin default 1 min 1 max 256");
+                println!("option name Hash type spin default 16 min 1 max 4096");
+                println!("option name MultiPV type spin default 1 min 1 max 256");
+                println!("option name Ponder type check default false");
+                println!(
+                    "option name Skill Level type spin default {0} min 0 max {0}",
+                    MAX_SKILL_LEVEL
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!(
+                    "option name UCI_Variant type combo default {}{}",
+                    Variant::Standard,
+                    Variant::ALL
+                        .iter()
+                        .map(|variant| format!(" var {}", variant))
+                        .collect::<String>()
+                );
+                println!("option name UCI_LimitStrength type check default false");
+                println!(
+                    "option name UCI_Elo type spin default {} min {} max {}",
+                    MAX_ELO, MIN_ELO, MAX_ELO
+                );
+                println!("option name OwnBook type check default false");
+                println!("option name BookFile type string default <empty>");
+                println!("option name BookKeys type string default <empty>");
+                println!("option name SyzygyPath type string default <empty>");
                 println!("uciok");
             }
             Some(&"isready") => {
                 println!("readyok");
             }
             Some(&"ucinewgame") => {
-                // TODO: reset
+                engine.stop();
+                engine.position = Position::initial();
 
-                println!("uciok");
+                if let Some(tt) = &engine.options.tt {
+                    tt.clear();
+                }
+            }
+            Some(&"setoption") => {
+                engine.set_option(&parts);
+            }
+            Some(&"position") => {
+                engine.stop();
+                engine.set_position(&parts);
+            }
+            Some(&"go") => {
+                engine.go(&parts);
+            }
+            Some(&"ponderhit") => {
+                engine.ponderhit();
             }
             Some(&"stop") => {
-                // TODO: stop
+                engine.stop();
             }
             Some(&"quit") => {
-                // TODO: quit
+                engine.stop();
 
                 break;
             }
@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use psce_core::Color;
+
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+const MOVE_OVERHEAD_MS: u64 = 30;
+
+/// Clock state from a `go` command, indexed by color.
+#[derive(Default, Debug)]
+pub struct TimeControl {
+    pub time: [Option<u64>; 2],
+    pub increment: [Option<u64>; 2],
+    pub moves_to_go: Option<u64>,
+}
+
+impl TimeControl {
+    /// Allocates the time to spend on the current move, or `None` when the
+    /// search is not on the clock.
+    pub fn allocate(&self, color: Color) -> Option<Duration> {
+        let time = self.time[color as usize]?;
+        let increment = self.increment[color as usize].unwrap_or(0);
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+        let budget = time / moves_to_go + increment * 3 / 4;
+        let limit = time.saturating_sub(MOVE_OVERHEAD_MS);
+
+        Some(Duration::from_millis(budget.min(limit).max(1)))
+    }
+}